@@ -0,0 +1,249 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use reqwest::Client;
+use serenity::all::{ChannelId, Colour, CreateMessage, Http};
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+use crate::{
+    matches::get_match_info,
+    shared::{
+        requests::{request_league_entries, request_match, request_matches_from_puuid},
+        static_data::{arena_augments, queue_name},
+        store::{LinkedAccount, Store},
+        types::{solo_queue_entry, AccountInfoContext, DiscordOutput, MatchDto},
+        util::RateLimiter,
+    },
+    tilt::{check_tilt, is_ranked_queue},
+};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(120);
+/// How many of each player's latest match ids are compared against the last announced one
+const RECENT_MATCH_COUNT: i64 = 5;
+pub const RANKED_SOLO_QUEUE_ID: i64 = 420;
+
+type PollError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Background task started from `ready()`, posts a summary for every newly finished game
+/// of a linked player in each guild that configured an announcement channel
+pub async fn poll_finished_games(
+    http: Arc<Http>,
+    client: Client,
+    api_key: String,
+    store: Arc<Store>,
+    limiter: Arc<RateLimiter>,
+) {
+    loop {
+        if let Err(err) = announce_finished_games(&http, &client, &api_key, &store, &limiter).await
+        {
+            warn!("Polling finished games failed: {}", err);
+        }
+        sleep(POLL_INTERVAL).await;
+    }
+}
+
+async fn announce_finished_games(
+    http: &Http,
+    client: &Client,
+    api_key: &str,
+    store: &Store,
+    limiter: &RateLimiter,
+) -> Result<(), PollError> {
+    // One account can be linked in several guilds, poll it once and post everywhere
+    let mut targets: HashMap<String, (LinkedAccount, Vec<(u64, ChannelId)>)> = HashMap::new();
+    {
+        let data = store.read().await;
+        for (guild_id, settings) in data.guild_settings.iter() {
+            let Some(channel) = settings.announcement_channel else {
+                continue;
+            };
+            for (_, account) in data.guild_accounts(*guild_id) {
+                targets
+                    .entry(account.puuid.clone())
+                    .or_insert_with(|| (account.clone(), vec![]))
+                    .1
+                    .push((*guild_id, ChannelId::new(channel)));
+            }
+        }
+    }
+
+    let no_augments = HashMap::new();
+    let augments = arena_augments(client).await.unwrap_or(&no_augments);
+
+    for (account, guilds) in targets.into_values() {
+        // One account failing shouldn't stop the sweep for everyone after it
+        if let Err(err) = announce_account(
+            http, &account, &guilds, augments, client, api_key, store, limiter,
+        )
+        .await
+        {
+            warn!("Announcing games of {} failed: {}", account.riot_id(), err);
+        }
+    }
+
+    Ok(())
+}
+
+/// Posts the account's games finished since the last poll in each of `guilds`
+#[allow(clippy::too_many_arguments)]
+async fn announce_account(
+    http: &Http,
+    account: &LinkedAccount,
+    guilds: &[(u64, ChannelId)],
+    augments: &HashMap<i64, String>,
+    client: &Client,
+    api_key: &str,
+    store: &Store,
+    limiter: &RateLimiter,
+) -> Result<(), PollError> {
+    let puuid = &account.puuid;
+    let account_info_context = AccountInfoContext {
+        puuid: puuid.clone(),
+        player_name: account.player_name.clone(),
+        tag: account.tag.clone(),
+        platform: account.platform,
+    };
+    limiter.acquire_background().await;
+    let match_ids =
+        request_matches_from_puuid(RECENT_MATCH_COUNT, api_key, account_info_context, client)
+            .await?;
+    let Some(newest) = match_ids.first().cloned() else {
+        return Ok(());
+    };
+
+    let last_seen = store.read().await.last_seen_match.get(puuid).cloned();
+    let new_match_ids: Vec<&String> = match &last_seen {
+        // First poll for this account, only remember where history starts
+        None => vec![],
+        Some(last_seen) => match_ids.iter().take_while(|id| *id != last_seen).collect(),
+    };
+
+    let mut matches = vec![];
+    for match_id in new_match_ids.into_iter().rev() {
+        limiter.acquire_background().await;
+        matches.push(request_match(match_id, account.platform, api_key, client).await?);
+    }
+    store.save_matches(puuid, &matches).await?;
+    // Only moves on once every new game is cached, a failed fetch retries them next poll
+    store
+        .update(|data| data.last_seen_match.insert(puuid.clone(), newest))
+        .await?;
+
+    let ranked_games = matches
+        .iter()
+        .filter(|m| m.info.queue_id == RANKED_SOLO_QUEUE_ID)
+        .count();
+    let lp_change = if ranked_games > 0 || last_seen.is_none() {
+        update_solo_rank(account, api_key, client, store, limiter)
+            .await
+            .unwrap_or_else(|err| {
+                warn!(
+                    "Could not update the rank of {}: {}",
+                    account.riot_id(),
+                    err
+                );
+                None
+            })
+    } else {
+        None
+    };
+
+    let played_ranked = matches.iter().any(|m| is_ranked_queue(m.info.queue_id));
+    for match_dto in matches {
+        let is_ranked = match_dto.info.queue_id == RANKED_SOLO_QUEUE_ID;
+        let Some(output) = get_announcement(
+            match_dto,
+            account,
+            lp_change.filter(|_| is_ranked),
+            ranked_games,
+            augments,
+        ) else {
+            continue;
+        };
+
+        for (_, channel) in guilds.iter() {
+            let message = CreateMessage::new().embed(output.to_embed());
+            if let Err(err) = channel.send_message(http, message).await {
+                warn!("Could not post announcement in {}: {}", channel, err);
+            }
+        }
+    }
+
+    if played_ranked {
+        if let Err(err) = check_tilt(http, account, guilds, store).await {
+            warn!("Checking tilt of {} failed: {}", account.riot_id(), err);
+        }
+    }
+
+    Ok(())
+}
+
+/// Stores the account's current solo queue entry, returning the LP change since the last one
+async fn update_solo_rank(
+    account: &LinkedAccount,
+    api_key: &str,
+    client: &Client,
+    store: &Store,
+    limiter: &RateLimiter,
+) -> Result<Option<i32>, PollError> {
+    limiter.acquire_background().await;
+    let entries = request_league_entries(&account.puuid, account.platform, api_key, client).await?;
+    let Some(entry) = solo_queue_entry(&entries).cloned() else {
+        return Ok(None);
+    };
+
+    let previous = store
+        .update(|data| data.solo_rank.insert(account.puuid.clone(), entry.clone()))
+        .await?;
+    info!(
+        "{}#{} solo rank is now {}",
+        account.player_name,
+        account.tag,
+        entry.rank_string()
+    );
+
+    Ok(previous.map(|previous| entry.absolute_league_points() - previous.absolute_league_points()))
+}
+
+fn get_announcement(
+    match_dto: MatchDto,
+    account: &LinkedAccount,
+    lp_change: Option<i32>,
+    ranked_games: usize,
+    augments: &HashMap<i64, String>,
+) -> Option<DiscordOutput> {
+    let queue = queue_name(match_dto.info.queue_id);
+    // Remakes aren't worth a post
+    let ((_, body, _), win) = get_match_info(match_dto, 1, account.puuid.clone(), augments).ok()?;
+    let win = win?;
+
+    let mut description = queue.to_string();
+    if let Some(lp_change) = lp_change {
+        description.push_str(&format!(" - {:+} LP", lp_change));
+        if ranked_games > 1 {
+            description.push_str(&format!(" (over {} games)", ranked_games));
+        }
+    }
+
+    Some(DiscordOutput::new(
+        if win {
+            Colour::DARK_GREEN
+        } else {
+            Colour::DARK_RED
+        },
+        description,
+        vec![(
+            if win { "Victory" } else { "Defeat" }.to_string(),
+            body,
+            false,
+        )],
+        "".to_string(),
+        format!(
+            "{}#{} {} a game",
+            account.player_name,
+            account.tag,
+            if win { "won" } else { "lost" }
+        ),
+        "".to_string(),
+    ))
+}
@@ -23,12 +23,14 @@ struct RuneBuild {
     shards: RuneBuildInfo,
 }
 
-type SkillOrderInfo = Vec<Vec<bool>>;
+pub type SkillOrderInfo = Vec<Vec<bool>>;
 
 use crate::shared::types::DiscordOutput;
 
 const TRANSPARENT_CIRCLE: &str = "⚫";
 const SELECTED_CIRCLE: &str = "⚪";
+const DIFFERENT_CIRCLE: &str = "🔴";
+const RECOMMENDED_CIRCLE: &str = "🟢";
 const RUNE_COLUMN_WIDTH: usize = 15;
 
 #[instrument(skip(tab), fields(champion1 = champion1, champion2 = champion2, lane = lane))]
@@ -148,11 +150,13 @@ pub fn handle_build_continuation(
         footer: previous_output.footer,
         title: previous_output.title,
         content: previous_output.content,
+        buttons: previous_output.buttons,
+        attachments: previous_output.attachments,
     }
 }
 
 #[instrument(skip(tab), fields(champion1 = champion1, champion2 = champion2, lane = lane))]
-async fn get_u_gg_document_body(
+pub async fn get_u_gg_document_body(
     champion1: &str,
     champion2: Option<&str>,
     lane: Option<&str>,
@@ -509,41 +513,59 @@ fn grid_to_row(row: &Vec<&str>) -> String {
     result
 }
 
+/// u.gg's recommended item groups with the item names in each, in page order
+#[instrument(skip(tab))]
+pub fn get_item_build(
+    tab: &Arc<Tab>,
+) -> Result<Vec<(&'static str, Vec<String>)>, Box<dyn std::error::Error>> {
+    info!("get_item_build called");
+
+    let item_groups = vec![
+        (
+            "Starting Items",
+            ".recommended-build_items .starting-items .item-img",
+        ),
+        (
+            "Core Items",
+            ".recommended-build_items .core-items .image-wrapper",
+        ),
+        (
+            "Fourth Item Options",
+            ".recommended-build_items .item-options-1 .item-img",
+        ),
+        (
+            "Fifth Item Options",
+            ".recommended-build_items .item-options-2 .item-img",
+        ),
+        (
+            "Sixth Item Options",
+            ".recommended-build_items .item-options-3 .item-img",
+        ),
+    ];
+
+    let mut result = Vec::new();
+    for (item_group, selector) in item_groups {
+        result.push((item_group, find_names_for_items(tab, selector)?));
+    }
+
+    Ok(result)
+}
+
 #[instrument(skip(tab))]
 fn generate_item_build_info(tab: &Arc<Tab>) -> Result<String, Box<dyn std::error::Error>> {
     info!("generate_item_build_info called");
 
-    let starting_items_selector = ".recommended-build_items .starting-items .item-img";
-    let core_items_selector = ".recommended-build_items .core-items .image-wrapper";
-    let fourth_item_options_selector = ".recommended-build_items .item-options-1 .item-img";
-    let fifth_item_options_selector = ".recommended-build_items .item-options-2 .item-img";
-    let sixth_item_options_selector = ".recommended-build_items .item-options-3 .item-img";
-
-    let mut starting_items = "    ".to_string();
-    starting_items.push_str(&find_names_for_items(&tab, starting_items_selector)?.join("\n    "));
-    let mut core_items = "    ".to_string();
-    core_items.push_str(&find_names_for_items(&tab, core_items_selector)?.join("\n    "));
-    let mut fourth_item_options = "    ".to_string();
-    fourth_item_options
-        .push_str(&find_names_for_items(&tab, fourth_item_options_selector)?.join("\n    "));
-    let mut fifth_item_options = "    ".to_string();
-    fifth_item_options
-        .push_str(&find_names_for_items(&tab, fifth_item_options_selector)?.join("\n    "));
-    let mut sixth_item_options = "    ".to_string();
-    sixth_item_options
-        .push_str(&find_names_for_items(&tab, sixth_item_options_selector)?.join("\n    "));
-
-    let result = vec![
-        ("Starting Items", starting_items),
-        ("Core Items", core_items),
-        ("Fourth Item Options", fourth_item_options),
-        ("Fifth Item Options", fifth_item_options),
-        ("Sixth Item Options", sixth_item_options),
-    ]
-    .into_iter()
-    .fold(String::new(), |acc, (item_group, items)| {
-        format!("{}{}:\n{}\n", acc, item_group, items)
-    });
+    let result =
+        get_item_build(tab)?
+            .into_iter()
+            .fold(String::new(), |acc, (item_group, items)| {
+                let items = items
+                    .iter()
+                    .map(|item| format!("    > {}", item))
+                    .collect::<Vec<String>>()
+                    .join("\n");
+                format!("{}{}:\n{}\n", acc, item_group, items)
+            });
 
     info!("generate_item_build_info result: result = {:?}", result);
 
@@ -569,7 +591,7 @@ fn find_names_for_items(
         let tooltip_text = tooltip_element
             .get_inner_text()
             .expect("Failed to get tooltip text");
-        item_names.push(format!("> {}", tooltip_text));
+        item_names.push(tooltip_text);
     }
 
     info!("find_names_for_items result: item_names = {:?}", item_names);
@@ -581,6 +603,15 @@ fn find_names_for_items(
 fn generate_skill_order(document: &Html) -> Result<String, Box<dyn std::error::Error>> {
     info!("generate_skill_order called");
 
+    let output = generate_output_from_skills(get_skill_order(document)?, None);
+    Ok(output)
+}
+
+/// One row per skill (Q, W, E, R) with a cell per level, `true` where that skill is leveled
+#[instrument(skip(document))]
+pub fn get_skill_order(document: &Html) -> Result<SkillOrderInfo, Box<dyn std::error::Error>> {
+    info!("get_skill_order called");
+
     let skill_order_selector = Selector::parse(".skill-order-row").unwrap();
 
     let result: SkillOrderInfo = document
@@ -601,11 +632,15 @@ fn generate_skill_order(document: &Html) -> Result<String, Box<dyn std::error::E
         })
         .collect();
 
-    let output = generate_output_from_skills(result);
-    Ok(output)
+    Ok(result)
 }
 
-fn generate_output_from_skills(skills: SkillOrderInfo) -> String {
+/// Renders the skill order grid. With a `reference` order, levels where `skills` picked a
+/// different skill are marked red and the reference's pick at that level green
+pub fn generate_output_from_skills(
+    skills: SkillOrderInfo,
+    reference: Option<&SkillOrderInfo>,
+) -> String {
     info!("generate_output_from_skills called {:?}", skills);
 
     let mut transposed: Vec<Vec<String>> = vec![Vec::new(); 18]; // Assuming max 18 levels
@@ -619,12 +654,27 @@ fn generate_output_from_skills(skills: SkillOrderInfo) -> String {
     ]
     .join("   ");
 
-    skills.iter().for_each(|skill| {
+    let leveled_in_reference = |skill: usize, level: usize| {
+        reference.map(|reference| {
+            reference
+                .get(skill)
+                .and_then(|levels| levels.get(level))
+                .copied()
+                .unwrap_or(false)
+        })
+    };
+
+    skills.iter().enumerate().for_each(|(skill_index, skill)| {
         let mut i = 0;
         skill
             .iter()
             .map(|x| {
                 i += 1;
+                match (*x, leveled_in_reference(skill_index, i - 1)) {
+                    (true, Some(false)) => return DIFFERENT_CIRCLE.to_string(),
+                    (false, Some(true)) => return RECOMMENDED_CIRCLE.to_string(),
+                    _ => {}
+                }
                 if *x {
                     if i == 6 || i == 11 || i == 16 {
                         "🟡".to_string()
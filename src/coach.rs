@@ -0,0 +1,295 @@
+use std::{str::FromStr, sync::Arc};
+
+use headless_chrome::Tab;
+use reqwest::Client;
+use serenity::all::Colour;
+
+use crate::{
+    build::{generate_output_from_skills, get_item_build, get_skill_order, get_u_gg_document_body},
+    shared::{
+        requests::request_match,
+        static_data::{champions, find_champion, items},
+        store::Store,
+        types::{DiscordOutput, Platform, TimelineDto},
+        util::RateLimiter,
+    },
+    timeline::fetch_timeline,
+};
+
+const MAX_LEVEL: usize = 18;
+/// Purchases before this (milliseconds) count as starting items
+const STARTING_ITEMS_CUTOFF: i64 = 60_000;
+const MATCHING_ICON: &str = "✅";
+const DIFFERENT_ICON: &str = "❌";
+
+/// Puts the player's item and skill order from a game next to u.gg's build for the same
+/// champion and matchup, replayed from a `coach:{match_id}:{participant_id}` button
+pub async fn handle_coach_command(
+    match_id: &str,
+    participant_id: i32,
+    api_key: &str,
+    client: &Client,
+    store: &Store,
+    limiter: &RateLimiter,
+    tab: &Arc<Tab>,
+) -> Result<DiscordOutput, Box<dyn std::error::Error>> {
+    let platform = match_id
+        .split_once('_')
+        .and_then(|(platform, _)| Platform::from_str(platform).ok())
+        .ok_or_else(|| format!("Can't tell which region {} was played on", match_id))?;
+    let match_dto = match store.load_match(match_id) {
+        Some(match_dto) => match_dto,
+        None => {
+            limiter.acquire().await;
+            request_match(match_id, platform, api_key, client).await?
+        }
+    };
+    let me = match_dto
+        .info
+        .participants
+        .iter()
+        .find(|p| p.participant_id == participant_id)
+        .ok_or("Player not found in match")?;
+    store
+        .save_matches(&me.puuid, std::slice::from_ref(&match_dto))
+        .await?;
+    let opponent = match_dto.info.participants.iter().find(|p| {
+        !me.team_position.is_empty()
+            && p.team_id != me.team_id
+            && p.team_position == me.team_position
+    });
+
+    // A /matches reply carries up to 25 coach buttons, each click reuses the cached timeline
+    let timeline = fetch_timeline(match_id, platform, api_key, client, store, limiter).await?;
+    let (starting_items, completed_items) =
+        get_purchases(&timeline, participant_id, client).await?;
+    let skill_order = get_skill_levels(&timeline, participant_id);
+
+    let champions = champions(client).await?;
+    let u_gg_name = |champion_name: &str| {
+        find_champion(champions, champion_name)
+            .map(|champion| champion.u_gg_name())
+            .unwrap_or_else(|| champion_name.to_lowercase())
+    };
+    let me_u_gg = u_gg_name(&me.champion_name);
+    let opponent_u_gg = opponent.map(|opponent| u_gg_name(&opponent.champion_name));
+    let lane = match me.team_position.as_str() {
+        "TOP" => Some("top"),
+        "JUNGLE" => Some("jungle"),
+        "MIDDLE" => Some("mid"),
+        "BOTTOM" => Some("adc"),
+        "UTILITY" => Some("support"),
+        _ => None,
+    };
+
+    // Scraping last, nothing is awaited while the parsed page is alive
+    let document = get_u_gg_document_body(&me_u_gg, opponent_u_gg.as_deref(), lane, tab).await?;
+    let recommended_skill_order = get_skill_order(&document)?;
+    let recommended_items = get_item_build(tab)?;
+
+    let is_recommended = |item: &str, starting: bool| {
+        recommended_items
+            .iter()
+            .filter(|(group, _)| (*group == "Starting Items") == starting)
+            .flat_map(|(_, names)| names)
+            .any(|name| name.eq_ignore_ascii_case(item))
+    };
+    let icon = |matches: bool| {
+        if matches {
+            MATCHING_ICON
+        } else {
+            DIFFERENT_ICON
+        }
+    };
+
+    let mut your_items = vec![format!(
+        "Start: {}",
+        starting_items
+            .iter()
+            .map(|item| format!("{} {}", icon(is_recommended(item, true)), item))
+            .collect::<Vec<String>>()
+            .join(", ")
+    )];
+    your_items.extend(completed_items.iter().map(|(timestamp, item)| {
+        format!(
+            "{} {} {}",
+            format_timestamp(*timestamp),
+            icon(is_recommended(item, false)),
+            item
+        )
+    }));
+
+    let built = |item: &str| {
+        completed_items
+            .iter()
+            .any(|(_, name)| name.eq_ignore_ascii_case(item))
+    };
+    let u_gg_items = recommended_items
+        .iter()
+        .filter(|(group, _)| *group != "Starting Items")
+        .map(|(group, names)| {
+            format!(
+                "{}:\n{}",
+                group,
+                names
+                    .iter()
+                    .map(|name| format!("  {} {}", icon(built(name)), name))
+                    .collect::<Vec<String>>()
+                    .join("\n")
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let core_items = recommended_items
+        .iter()
+        .find(|(group, _)| *group == "Core Items")
+        .map(|(_, names)| names.as_slice())
+        .unwrap_or_default();
+    let matching_levels = (0..MAX_LEVEL)
+        .filter(|level| {
+            skill_order
+                .iter()
+                .zip(recommended_skill_order.iter())
+                .all(|(yours, recommended)| {
+                    yours.get(*level).copied().unwrap_or(false)
+                        == recommended.get(*level).copied().unwrap_or(false)
+                })
+        })
+        .count();
+
+    let fields = vec![
+        (
+            "Your Items".to_string(),
+            format!("```{}```", your_items.join("\n")),
+            true,
+        ),
+        (
+            "u.gg Items".to_string(),
+            format!("```{}```", u_gg_items),
+            true,
+        ),
+        ("\u{200b}".to_string(), "\u{200b}".to_string(), false),
+        (
+            "Your Skill Order".to_string(),
+            format!(
+                "```{}```",
+                generate_output_from_skills(skill_order, Some(&recommended_skill_order))
+            ),
+            true,
+        ),
+        (
+            "u.gg Skill Order".to_string(),
+            format!(
+                "```{}```",
+                generate_output_from_skills(recommended_skill_order, None)
+            ),
+            true,
+        ),
+    ];
+
+    Ok(DiscordOutput::new(
+        Colour::BLURPLE,
+        format!(
+            "{}/{} core items built, skill order matches u.gg at {}/{} levels\n🔴 your pick where u.gg differs, 🟢 u.gg's pick",
+            core_items.iter().filter(|item| built(item)).count(),
+            core_items.len(),
+            matching_levels,
+            MAX_LEVEL
+        ),
+        fields,
+        "".to_string(),
+        match opponent {
+            Some(opponent) => format!(
+                "🎓 {} vs. {} - {}",
+                me.champion_name, opponent.champion_name, match_id
+            ),
+            None => format!("🎓 {} - {}", me.champion_name, match_id),
+        },
+        "".to_string(),
+    ))
+}
+
+/// Starting item names, then completed items with when they were bought. Undone purchases
+/// are dropped
+async fn get_purchases(
+    timeline: &TimelineDto,
+    participant_id: i32,
+    client: &Client,
+) -> Result<(Vec<String>, Vec<(i64, String)>), Box<dyn std::error::Error>> {
+    let items = items(client).await?;
+
+    let mut purchases: Vec<(i64, i64)> = vec![];
+    for event in timeline
+        .info
+        .frames
+        .iter()
+        .flat_map(|frame| &frame.events)
+        .filter(|event| event.participant_id == Some(participant_id))
+    {
+        match event.event_type.as_str() {
+            "ITEM_PURCHASED" => purchases.extend(event.item_id.map(|id| (event.timestamp, id))),
+            "ITEM_UNDO" => {
+                if let Some(index) = purchases
+                    .iter()
+                    .rposition(|(_, id)| Some(*id) == event.before_id)
+                {
+                    purchases.remove(index);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let name = |id: &i64| {
+        items
+            .get(id)
+            .map(|item| item.name.clone())
+            .unwrap_or_else(|| format!("Item {}", id))
+    };
+    let starting_items = purchases
+        .iter()
+        .filter(|(timestamp, _)| *timestamp < STARTING_ITEMS_CUTOFF)
+        .map(|(_, id)| name(id))
+        .collect();
+    let completed_items = purchases
+        .iter()
+        .filter(|(timestamp, id)| {
+            *timestamp >= STARTING_ITEMS_CUTOFF
+                && items.get(id).is_some_and(|item| item.is_completed())
+        })
+        .map(|(timestamp, id)| (*timestamp, name(id)))
+        .collect();
+
+    Ok((starting_items, completed_items))
+}
+
+/// Same shape as u.gg's skill order, a row per skill with a cell per level
+fn get_skill_levels(timeline: &TimelineDto, participant_id: i32) -> Vec<Vec<bool>> {
+    let mut skill_order = vec![vec![false; MAX_LEVEL]; 4];
+    timeline
+        .info
+        .frames
+        .iter()
+        .flat_map(|frame| &frame.events)
+        .filter(|event| {
+            event.event_type == "SKILL_LEVEL_UP"
+                && event.participant_id == Some(participant_id)
+                // Evolutions don't spend a level
+                && event.level_up_type.as_deref() == Some("NORMAL")
+        })
+        .filter_map(|event| event.skill_slot)
+        .take(MAX_LEVEL)
+        .enumerate()
+        .for_each(|(level, skill_slot)| {
+            if let Some(skill) = skill_order.get_mut(skill_slot.wrapping_sub(1)) {
+                skill[level] = true;
+            }
+        });
+    skill_order
+}
+
+fn format_timestamp(timestamp: i64) -> String {
+    let seconds = timestamp / 1000;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
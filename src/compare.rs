@@ -0,0 +1,257 @@
+use std::collections::HashSet;
+
+use reqwest::Client;
+use serenity::all::Colour;
+
+use crate::{
+    duos::same_team,
+    matches::{fetch_matches, skipped_games_line},
+    shared::{
+        requests::{request_for_puuid, request_league_entries},
+        store::Store,
+        types::{solo_queue_entry, AccountInfoContext, DiscordOutput, MatchDto, Platform},
+        util::RateLimiter,
+    },
+    stats::{aggregate_by, find_participant, role_name, PerformanceTotals},
+};
+
+const TOP_ROLE_COUNT: usize = 2;
+const TOP_CHAMPION_COUNT: usize = 3;
+
+/// One side of the comparison
+struct ComparedPlayer {
+    riot_id: String,
+    puuid: String,
+    rank: String,
+    matches: Vec<MatchDto>,
+    /// Games that failed to load
+    skipped: usize,
+    totals: PerformanceTotals,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn handle_compare_command(
+    name_a: &str,
+    tag_a: &str,
+    name_b: &str,
+    tag_b: &str,
+    platform: Platform,
+    game_count: i64,
+    api_key: &str,
+    client: &Client,
+    store: &Store,
+    limiter: &RateLimiter,
+) -> Result<DiscordOutput, Box<dyn std::error::Error>> {
+    let a = fetch_player(
+        name_a, tag_a, platform, game_count, api_key, client, store, limiter,
+    )
+    .await?;
+    let b = fetch_player(
+        name_b, tag_b, platform, game_count, api_key, client, store, limiter,
+    )
+    .await?;
+    if a.puuid == b.puuid {
+        return Err("Pick two different players to compare".into());
+    }
+
+    let (winrate_a, winrate_b) = highlight(a.totals.winrate(), b.totals.winrate(), |v| {
+        format!("{:.0}%", v)
+    });
+    let (kda_a, kda_b) = highlight(a.totals.kda(), b.totals.kda(), |v| format!("{:.2}", v));
+    let (cs_a, cs_b) = highlight(a.totals.cs_per_minute(), b.totals.cs_per_minute(), |v| {
+        format!("{:.1}", v)
+    });
+    let (vision_a, vision_b) = highlight(
+        a.totals.vision_per_minute(),
+        b.totals.vision_per_minute(),
+        |v| format!("{:.2}", v),
+    );
+    let column = |player: &ComparedPlayer, winrate, kda, cs, vision| {
+        [
+            player.rank.clone(),
+            player.totals.games.to_string(),
+            winrate,
+            kda,
+            cs,
+            vision,
+        ]
+        .join("\n")
+    };
+
+    let fields = vec![
+        (
+            "\u{200b}".to_string(),
+            ["Rank", "Games", "Winrate", "KDA", "CS/min", "Vision/min"].join("\n"),
+            true,
+        ),
+        (
+            a.riot_id.clone(),
+            column(&a, winrate_a, kda_a, cs_a, vision_a),
+            true,
+        ),
+        (
+            b.riot_id.clone(),
+            column(&b, winrate_b, kda_b, cs_b, vision_b),
+            true,
+        ),
+        (format!("{} plays", a.riot_id), favourites(&a), true),
+        (format!("{} plays", b.riot_id), favourites(&b), true),
+        ("⚔️ Head to Head".to_string(), head_to_head(&a, &b), false),
+    ];
+
+    Ok(DiscordOutput::new(
+        Colour::BLURPLE,
+        format!(
+            "Last {} games each, remakes left out. Bold marks the better value{}",
+            game_count,
+            skipped_games_line(a.skipped + b.skipped)
+        ),
+        fields,
+        "".to_string(),
+        format!("{} vs. {}", a.riot_id, b.riot_id),
+        "".to_string(),
+    ))
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn fetch_player(
+    player_name: &str,
+    tag: &str,
+    platform: Platform,
+    game_count: i64,
+    api_key: &str,
+    client: &Client,
+    store: &Store,
+    limiter: &RateLimiter,
+) -> Result<ComparedPlayer, Box<dyn std::error::Error>> {
+    limiter.acquire().await;
+    let puuid = request_for_puuid(player_name, tag, platform, api_key, client).await?;
+    limiter.acquire().await;
+    let entries = request_league_entries(&puuid, platform, api_key, client).await?;
+
+    let account_info_context = AccountInfoContext {
+        puuid: puuid.clone(),
+        player_name: player_name.to_string(),
+        tag: tag.to_string(),
+        platform,
+    };
+    let (matches, skipped) = fetch_matches(
+        game_count,
+        api_key,
+        account_info_context,
+        client,
+        store,
+        limiter,
+    )
+    .await?;
+
+    let mut totals = PerformanceTotals::default();
+    for match_dto in matches.iter() {
+        if let Some(me) = find_participant(match_dto, &puuid) {
+            totals.add(me, &match_dto.info);
+        }
+    }
+
+    Ok(ComparedPlayer {
+        riot_id: format!("{}#{}", player_name, tag),
+        puuid,
+        rank: solo_queue_entry(&entries)
+            .map(|entry| entry.rank_string())
+            .unwrap_or_else(|| "Unranked".to_string()),
+        matches,
+        skipped,
+        totals,
+    })
+}
+
+/// Formats both values, bolding the higher one
+fn highlight<F>(a: f64, b: f64, format: F) -> (String, String)
+where
+    F: Fn(f64) -> String,
+{
+    let (a_text, b_text) = (format(a), format(b));
+    if a_text == b_text {
+        (a_text, b_text)
+    } else if a > b {
+        (format!("**{}**", a_text), b_text)
+    } else {
+        (a_text, format!("**{}**", b_text))
+    }
+}
+
+/// Most played roles and champions
+fn favourites(player: &ComparedPlayer) -> String {
+    let summary = |totals: Vec<(String, PerformanceTotals)>, count: usize| {
+        totals
+            .iter()
+            .take(count)
+            .map(|(name, totals)| format!("{} {} ({:.0}%)", name, totals.games, totals.winrate()))
+            .collect::<Vec<String>>()
+            .join("\n")
+    };
+    let roles = aggregate_by(&player.matches, &player.puuid, role_name);
+    let champions = aggregate_by(&player.matches, &player.puuid, |me, _| {
+        me.champion_name.clone()
+    });
+    if roles.is_empty() {
+        return "No recent games".to_string();
+    }
+
+    format!(
+        "{}\n\n{}",
+        summary(roles, TOP_ROLE_COUNT),
+        summary(champions, TOP_CHAMPION_COUNT)
+    )
+}
+
+/// Games where both players were in the lobby, from either player's history
+fn head_to_head(a: &ComparedPlayer, b: &ComparedPlayer) -> String {
+    let mut seen = HashSet::new();
+    let mut together = PerformanceTotals::default();
+    let mut against = (0, 0);
+    for match_dto in a.matches.iter().chain(b.matches.iter()) {
+        if !seen.insert(match_dto.metadata.match_id.as_str()) {
+            continue;
+        }
+        let (Some(me_a), Some(me_b)) = (
+            find_participant(match_dto, &a.puuid),
+            find_participant(match_dto, &b.puuid),
+        ) else {
+            continue;
+        };
+        if me_a.is_remake() {
+            continue;
+        }
+
+        if same_team(match_dto, me_a, me_b) {
+            together.add(me_a, &match_dto.info);
+        } else {
+            against.0 += 1;
+            // Arena has more than two teams, so both can lose
+            if me_a.win {
+                against.1 += 1;
+            }
+        }
+    }
+
+    let together = if together.games == 0 {
+        "No games together".to_string()
+    } else {
+        format!(
+            "Together: {} games, {}W {}L ({:.0}%)",
+            together.games,
+            together.wins,
+            together.games - together.wins,
+            together.winrate()
+        )
+    };
+    let against = if against.0 == 0 {
+        "No games against each other".to_string()
+    } else {
+        format!(
+            "Against: {} games, {} won {}",
+            against.0, a.riot_id, against.1
+        )
+    };
+    format!("{}\n{}", together, against)
+}
@@ -130,6 +130,8 @@ pub async fn handle_counters_command(
         fields,
         footer: "".to_string(),
         content: "".to_string(),
+        buttons: vec![],
+        attachments: vec![],
     })
 }
 
@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+
+use reqwest::Client;
+use serenity::all::{Colour, GuildId};
+
+use crate::{
+    matches::{fetch_matches, skipped_games_line},
+    shared::{
+        requests::request_for_puuid,
+        store::Store,
+        types::{AccountInfoContext, DiscordOutput, MatchDto, ParticipantDto, Platform},
+        util::RateLimiter,
+    },
+    stats::find_participant,
+};
+
+/// Teammates seen fewer times than this are treated as random fill
+const MIN_DUO_GAMES: usize = 2;
+const MAX_DUOS: usize = 10;
+const TOP_CHAMPION_COUNT: usize = 3;
+
+/// Games played on the same team as one teammate
+#[derive(Default)]
+struct DuoTotals {
+    riot_id: String,
+    games: usize,
+    wins: usize,
+    their_champions: HashMap<String, usize>,
+    my_champions: HashMap<String, usize>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn handle_duos_command(
+    player_name: &str,
+    tag: &str,
+    platform: Platform,
+    game_count: i64,
+    guild_id: GuildId,
+    api_key: &str,
+    client: &Client,
+    store: &Store,
+    limiter: &RateLimiter,
+) -> Result<DiscordOutput, Box<dyn std::error::Error>> {
+    limiter.acquire().await;
+    let puuid = request_for_puuid(player_name, tag, platform, api_key, client).await?;
+
+    let account_info_context = AccountInfoContext {
+        puuid: puuid.clone(),
+        player_name: player_name.to_string(),
+        tag: tag.to_string(),
+        platform,
+    };
+    let (matches, skipped) = fetch_matches(
+        game_count,
+        api_key,
+        account_info_context,
+        client,
+        store,
+        limiter,
+    )
+    .await?;
+
+    let mut duos: HashMap<String, DuoTotals> = HashMap::new();
+    let mut games = 0;
+    for match_dto in matches.iter() {
+        let Some(me) = find_participant(match_dto, &puuid).filter(|me| !me.is_remake()) else {
+            continue;
+        };
+        games += 1;
+        for teammate in teammates(match_dto, me) {
+            let duo = duos.entry(teammate.puuid.clone()).or_default();
+            // Riot IDs can change, the most recent game has the current one
+            if duo.riot_id.is_empty() {
+                duo.riot_id = riot_id(teammate);
+            }
+            duo.games += 1;
+            duo.wins += me.win as usize;
+            *duo.their_champions
+                .entry(teammate.champion_name.clone())
+                .or_default() += 1;
+            *duo.my_champions
+                .entry(me.champion_name.clone())
+                .or_default() += 1;
+        }
+    }
+
+    let mut duos: Vec<(String, DuoTotals)> = duos
+        .into_iter()
+        .filter(|(_, duo)| duo.games >= MIN_DUO_GAMES)
+        .collect();
+    duos.sort_by(|(_, a), (_, b)| b.games.cmp(&a.games).then(b.wins.cmp(&a.wins)));
+
+    let title = format!("{}#{}'s Duos", player_name, tag);
+    if duos.is_empty() {
+        return Ok(DiscordOutput::new(
+            Colour::LIGHT_GREY,
+            format!(
+                "No teammate showed up in more than one of the last {} games{}",
+                games,
+                skipped_games_line(skipped)
+            ),
+            vec![],
+            "".to_string(),
+            title,
+            "".to_string(),
+        ));
+    }
+
+    let linked_users: HashMap<String, u64> = store
+        .read()
+        .await
+        .guild_accounts(guild_id.get())
+        .into_iter()
+        .map(|(user_id, account)| (account.puuid.clone(), user_id))
+        .collect();
+
+    let fields = duos
+        .iter()
+        .take(MAX_DUOS)
+        .map(|(teammate_puuid, duo)| {
+            let mut lines = vec![];
+            if let Some(user_id) = linked_users.get(teammate_puuid) {
+                lines.push(format!("<@{}>", user_id));
+            }
+            lines.push(format!(
+                "{} games - {:.0}% WR",
+                duo.games,
+                duo.wins as f64 / duo.games as f64 * 100.0
+            ));
+            lines.push(format!(
+                "They played {}",
+                top_champions(&duo.their_champions)
+            ));
+            lines.push(format!("You played {}", top_champions(&duo.my_champions)));
+            (duo.riot_id.clone(), lines.join("\n"), true)
+        })
+        .collect();
+
+    Ok(DiscordOutput::new(
+        Colour::DARK_GREEN,
+        format!(
+            "Teammates seen in at least {} of the last {} games{}",
+            MIN_DUO_GAMES,
+            games,
+            skipped_games_line(skipped)
+        ),
+        fields,
+        "".to_string(),
+        title,
+        "".to_string(),
+    ))
+}
+
+/// Everyone else on the player's team
+fn teammates<'a>(
+    match_dto: &'a MatchDto,
+    me: &'a ParticipantDto,
+) -> impl Iterator<Item = &'a ParticipantDto> {
+    match_dto
+        .info
+        .participants
+        .iter()
+        .filter(move |p| p.puuid != me.puuid && same_team(match_dto, me, p))
+}
+
+/// Arena teams are the players' subteams rather than the two sides
+pub fn same_team(match_dto: &MatchDto, a: &ParticipantDto, b: &ParticipantDto) -> bool {
+    if match_dto.info.game_mode == "CHERRY" {
+        a.player_subteam_id == b.player_subteam_id
+    } else {
+        a.team_id == b.team_id
+    }
+}
+
+fn riot_id(participant: &ParticipantDto) -> String {
+    if participant.riot_id_tagline.is_empty() {
+        participant.riot_id_game_name.clone()
+    } else {
+        format!(
+            "{}#{}",
+            participant.riot_id_game_name, participant.riot_id_tagline
+        )
+    }
+}
+
+/// Most played first, e.g. `Thresh 3, Nautilus 1`
+fn top_champions(champions: &HashMap<String, usize>) -> String {
+    let mut champions: Vec<(&String, &usize)> = champions.iter().collect();
+    champions.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+    champions
+        .iter()
+        .take(TOP_CHAMPION_COUNT)
+        .map(|(champion, games)| format!("{} {}", champion, games))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
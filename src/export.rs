@@ -0,0 +1,163 @@
+use chrono::DateTime;
+use reqwest::Client;
+use serde::Serialize;
+use serenity::all::Colour;
+
+use crate::{
+    matches::{fetch_matches, skipped_games_line},
+    shared::{
+        requests::request_for_puuid,
+        static_data::queue_name,
+        store::Store,
+        types::{AccountInfoContext, DiscordOutput, MatchDto, Platform},
+        util::RateLimiter,
+    },
+    stats::{find_participant, role_name},
+};
+
+pub const EXPORT_FORMATS: [&str; 2] = ["csv", "json"];
+
+/// One row per game, field order is the CSV column order
+#[derive(Debug, Serialize)]
+struct ExportRow {
+    match_id: String,
+    date: String,
+    queue: String,
+    champion: String,
+    role: String,
+    kills: i32,
+    deaths: i32,
+    assists: i32,
+    cs: i32,
+    gold: i32,
+    damage: i32,
+    vision: i32,
+    win: bool,
+    remake: bool,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn handle_export_command(
+    player_name: &str,
+    tag: &str,
+    platform: Platform,
+    game_count: i64,
+    format: &str,
+    api_key: &str,
+    client: &Client,
+    store: &Store,
+    limiter: &RateLimiter,
+) -> Result<DiscordOutput, Box<dyn std::error::Error>> {
+    if !EXPORT_FORMATS.contains(&format) {
+        return Err(format!("Unknown export format {}", format).into());
+    }
+
+    limiter.acquire().await;
+
+    let puuid = request_for_puuid(player_name, tag, platform, api_key, client).await?;
+
+    let account_info_context = AccountInfoContext {
+        puuid: puuid.clone(),
+        player_name: player_name.to_string(),
+        tag: tag.to_string(),
+        platform,
+    };
+
+    // Matches already in the store are read from disk, so re-exports only fetch new games
+    let (matches, skipped) = fetch_matches(
+        game_count,
+        api_key,
+        account_info_context,
+        client,
+        store,
+        limiter,
+    )
+    .await?;
+    let rows: Vec<ExportRow> = matches
+        .iter()
+        .filter_map(|match_dto| export_row(match_dto, &puuid))
+        .collect();
+
+    let contents = match format {
+        "json" => serde_json::to_vec_pretty(&rows)?,
+        _ => to_csv(&rows).into_bytes(),
+    };
+    let file_name = format!("{}_{}_matches.{}", player_name, tag, format).replace(' ', "_");
+
+    Ok(DiscordOutput::new(
+        Colour::DARK_GREEN,
+        format!(
+            "{} games exported as {}{}",
+            rows.len(),
+            format.to_uppercase(),
+            skipped_games_line(skipped)
+        ),
+        vec![],
+        "".to_string(),
+        format!("{}#{}'s Match Export", player_name, tag),
+        "".to_string(),
+    )
+    .with_attachment(file_name, contents))
+}
+
+fn export_row(match_dto: &MatchDto, puuid: &str) -> Option<ExportRow> {
+    let me = find_participant(match_dto, puuid)?;
+    let info = &match_dto.info;
+
+    Some(ExportRow {
+        match_id: match_dto.metadata.match_id.clone(),
+        date: DateTime::from_timestamp_millis(info.game_start_timestamp)
+            .map(|date| date.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default(),
+        queue: queue_name(info.queue_id).to_string(),
+        champion: me.champion_name.clone(),
+        role: role_name(me, info),
+        kills: me.kills,
+        deaths: me.deaths,
+        assists: me.assists,
+        cs: me.total_minions_killed + me.neutral_minions_killed,
+        gold: me.gold_earned,
+        damage: me.total_damage_dealt_to_champions,
+        vision: me.vision_score,
+        win: me.win && !me.is_remake(),
+        remake: me.is_remake(),
+    })
+}
+
+fn to_csv(rows: &[ExportRow]) -> String {
+    let header =
+        "match_id,date,queue,champion,role,kills,deaths,assists,cs,gold,damage,vision,win,remake";
+    let lines = rows.iter().map(|row| {
+        [
+            csv_field(&row.match_id),
+            csv_field(&row.date),
+            csv_field(&row.queue),
+            csv_field(&row.champion),
+            csv_field(&row.role),
+            row.kills.to_string(),
+            row.deaths.to_string(),
+            row.assists.to_string(),
+            row.cs.to_string(),
+            row.gold.to_string(),
+            row.damage.to_string(),
+            row.vision.to_string(),
+            row.win.to_string(),
+            row.remake.to_string(),
+        ]
+        .join(",")
+    });
+
+    std::iter::once(header.to_string())
+        .chain(lines)
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Quotes fields containing commas, quotes or newlines as spreadsheets expect
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+
+use plotters::prelude::*;
+use reqwest::Client;
+use serenity::all::Colour;
+use tracing::warn;
+
+use crate::{
+    matches::{fetch_matches, skipped_games_line},
+    shared::{
+        charts::{encode_png, register_fonts},
+        requests::request_for_puuid,
+        store::Store,
+        types::{AccountInfoContext, DiscordOutput, Platform},
+        util::RateLimiter,
+    },
+    stats::find_participant,
+    timeline::fetch_timeline,
+};
+
+/// Stylised stand-in for the Summoner's Rift minimap drawn for the bot, not Riot's artwork.
+/// Only the lanes, river and bases line up with the map, blue base bottom left
+static MINIMAP: &[u8] = include_bytes!("../assets/maps/summoners_rift.png");
+/// Upper bounds of Summoner's Rift map coordinates
+const MAP_MAX_X: f64 = 14870.0;
+const MAP_MAX_Y: f64 = 14980.0;
+const KILL_COLOUR: RGBColor = RGBColor(67, 181, 129);
+const DEATH_COLOUR: RGBColor = RGBColor(240, 71, 71);
+const MARKER_RADIUS: i32 = 9;
+
+#[allow(clippy::too_many_arguments)]
+pub async fn handle_heatmap_command(
+    player_name: &str,
+    tag: &str,
+    platform: Platform,
+    game_count: i64,
+    api_key: &str,
+    client: &Client,
+    store: &Store,
+    limiter: &RateLimiter,
+) -> Result<DiscordOutput, Box<dyn std::error::Error>> {
+    limiter.acquire().await;
+    let puuid = request_for_puuid(player_name, tag, platform, api_key, client).await?;
+
+    let account_info_context = AccountInfoContext {
+        puuid: puuid.clone(),
+        player_name: player_name.to_string(),
+        tag: tag.to_string(),
+        platform,
+    };
+    let (matches, skipped) = fetch_matches(
+        game_count,
+        api_key,
+        account_info_context,
+        client,
+        store,
+        limiter,
+    )
+    .await?;
+
+    // Positions only line up with the minimap on Summoner's Rift
+    let mut kills = vec![];
+    let mut deaths = vec![];
+    let mut games = 0;
+    let mut failed = 0;
+    for match_dto in matches.iter().filter(|m| m.info.game_mode == "CLASSIC") {
+        let Some(me) = find_participant(match_dto, &puuid).filter(|me| !me.is_remake()) else {
+            continue;
+        };
+        let match_id = &match_dto.metadata.match_id;
+        // One timeline per game, paced so large counts don't burst past the key's limit
+        let timeline =
+            match fetch_timeline(match_id, platform, api_key, client, store, limiter).await {
+                Ok(timeline) => timeline,
+                Err(err) => {
+                    warn!("Skipping the timeline of {}: {}", match_id, err);
+                    failed += 1;
+                    continue;
+                }
+            };
+        games += 1;
+
+        for event in timeline
+            .info
+            .frames
+            .iter()
+            .flat_map(|frame| &frame.events)
+            .filter(|event| event.event_type == "CHAMPION_KILL")
+        {
+            let Some(position) = &event.position else {
+                continue;
+            };
+            if event.killer_id == Some(me.participant_id) {
+                kills.push((position.x, position.y));
+            } else if event.victim_id == Some(me.participant_id) {
+                deaths.push((position.x, position.y));
+            }
+        }
+    }
+
+    let title = format!("{}#{}'s Kill & Death Heatmap", player_name, tag);
+    if games == 0 && failed > 0 {
+        return Err("Could not load any match timelines, try again later".into());
+    }
+    if games == 0 {
+        return Ok(DiscordOutput::new(
+            Colour::LIGHT_GREY,
+            "No Summoner's Rift games in this range".to_string(),
+            vec![],
+            "".to_string(),
+            title,
+            "".to_string(),
+        ));
+    }
+
+    let fields = vec![
+        ("🗡️ Kills by Area".to_string(), area_breakdown(&kills), true),
+        (
+            "💀 Deaths by Area".to_string(),
+            area_breakdown(&deaths),
+            true,
+        ),
+    ];
+    let heatmap = draw_heatmap(&kills, &deaths)?;
+
+    Ok(DiscordOutput::new(
+        Colour::DARK_GREEN,
+        format!(
+            "{} kills and {} deaths over {} Summoner's Rift games{}{}",
+            kills.len(),
+            deaths.len(),
+            games,
+            match failed {
+                0 => "".to_string(),
+                failed => format!(", {} timelines could not be loaded", failed),
+            },
+            skipped_games_line(skipped)
+        ),
+        fields,
+        "".to_string(),
+        title,
+        "".to_string(),
+    )
+    .with_attachment("heatmap.png".to_string(), heatmap))
+}
+
+/// Rough area of the map a position is in, lanes run along the edges and the middle diagonal
+fn map_area(position: (i32, i32)) -> &'static str {
+    let x = position.0 as f64 / MAP_MAX_X;
+    let y = position.1 as f64 / MAP_MAX_Y;
+    if x < 0.15 && y < 0.15 {
+        "Blue Base"
+    } else if x > 0.85 && y > 0.85 {
+        "Red Base"
+    } else if (x - y).abs() < 0.08 {
+        "Mid Lane"
+    } else if x < 0.15 || y > 0.85 {
+        "Top Lane"
+    } else if y < 0.15 || x > 0.85 {
+        "Bot Lane"
+    } else if (x + y - 1.0).abs() < 0.07 {
+        "River"
+    } else {
+        "Jungle"
+    }
+}
+
+fn area_breakdown(positions: &[(i32, i32)]) -> String {
+    if positions.is_empty() {
+        return "None".to_string();
+    }
+
+    let mut areas: HashMap<&str, usize> = HashMap::new();
+    for position in positions {
+        *areas.entry(map_area(*position)).or_default() += 1;
+    }
+    let mut areas: Vec<(&str, usize)> = areas.into_iter().collect();
+    areas.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+    areas
+        .iter()
+        .map(|(area, count)| {
+            format!(
+                "{} - {} ({:.0}%)",
+                area,
+                count,
+                *count as f64 / positions.len() as f64 * 100.0
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Kills on the left minimap and deaths on the right, overlapping markers stack up into the
+/// hot spots
+fn draw_heatmap(
+    kills: &[(i32, i32)],
+    deaths: &[(i32, i32)],
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    register_fonts();
+
+    let minimap = image::load_from_memory(MINIMAP)?.to_rgb8();
+    let (size, _) = minimap.dimensions();
+    let (width, height) = (size * 2, size);
+
+    // Both halves start out as the minimap, row by row
+    let mut buffer = Vec::with_capacity((width * height * 3) as usize);
+    for row in minimap.as_raw().chunks((size * 3) as usize) {
+        buffer.extend_from_slice(row);
+        buffer.extend_from_slice(row);
+    }
+
+    {
+        let root = BitMapBackend::with_buffer(&mut buffer, (width, height)).into_drawing_area();
+        let (left, right) = root.split_horizontally(size);
+
+        for (area, positions, colour, label) in [
+            (&left, kills, KILL_COLOUR, "Kills"),
+            (&right, deaths, DEATH_COLOUR, "Deaths"),
+        ] {
+            // Map y grows upwards, image y downwards
+            let to_pixel = |position: &(i32, i32)| {
+                (
+                    (position.0 as f64 / MAP_MAX_X * size as f64) as i32,
+                    ((1.0 - position.1 as f64 / MAP_MAX_Y) * size as f64) as i32,
+                )
+            };
+            area.draw(&Rectangle::new(
+                [(0, 0), (size as i32 - 1, size as i32 - 1)],
+                WHITE.mix(0.4),
+            ))?;
+            for position in positions {
+                area.draw(&Circle::new(
+                    to_pixel(position),
+                    MARKER_RADIUS,
+                    colour.mix(0.45).filled(),
+                ))?;
+            }
+            area.draw(&Text::new(
+                format!("{} ({})", label, positions.len()),
+                (12, 12),
+                ("sans-serif", 22).into_font().color(&WHITE),
+            ))?;
+        }
+
+        root.present()?;
+    }
+
+    encode_png(buffer, width, height)
+}
@@ -0,0 +1,168 @@
+use chrono::{Duration, Utc};
+use serenity::all::{Colour, GuildId};
+
+use crate::{
+    shared::{
+        store::{LinkedAccount, Store},
+        types::DiscordOutput,
+    },
+    stats::{find_participant, PerformanceTotals},
+};
+
+const PAGE_SIZE: usize = 10;
+
+pub const LEADERBOARD_METRICS: [&str; 4] = ["rank", "winrate", "kda", "games"];
+
+/// Ranks the guild's linked members using only what the store already has: rank snapshots
+/// from the rank poller and matches cached by /link, /matches, /stats and announcements.
+/// Members with alts are ranked by their highest account and their games are added up
+pub async fn handle_leaderboard_command(
+    guild_id: GuildId,
+    metric: &str,
+    days: i64,
+    page: usize,
+    store: &Store,
+) -> Result<DiscordOutput, Box<dyn std::error::Error>> {
+    if !LEADERBOARD_METRICS.contains(&metric) {
+        return Err(format!("Unknown leaderboard metric {}", metric).into());
+    }
+
+    let members: Vec<(u64, Vec<LinkedAccount>)> = store
+        .read()
+        .await
+        .guild_members(guild_id.get())
+        .into_iter()
+        .map(|(user_id, accounts)| (user_id, accounts.to_vec()))
+        .collect();
+    if members.is_empty() {
+        return Ok(DiscordOutput::new(
+            Colour::LIGHT_GREY,
+            "Nobody in this server has linked an account yet, use /link".to_string(),
+            vec![],
+            "".to_string(),
+            "Leaderboard".to_string(),
+            "".to_string(),
+        ));
+    }
+
+    // (user id, sort key, shown value), unranked players and players without games sort last
+    let mut rows: Vec<(u64, Option<f64>, String)> = vec![];
+    if metric == "rank" {
+        let data = store.read().await;
+        for (user_id, accounts) in members {
+            let highest = accounts
+                .iter()
+                .filter_map(|account| Some((account, data.latest_solo_rank(&account.puuid)?)))
+                .max_by_key(|(_, entry)| entry.absolute_league_points());
+            rows.push(match highest {
+                Some((account, entry)) => (
+                    user_id,
+                    Some(entry.absolute_league_points() as f64),
+                    format!(
+                        "{} ({}W {}L){}",
+                        entry.rank_string(),
+                        entry.wins,
+                        entry.losses,
+                        if accounts.len() > 1 {
+                            format!(" on {}", account.riot_id())
+                        } else {
+                            "".to_string()
+                        }
+                    ),
+                ),
+                None => (user_id, None, "Unranked".to_string()),
+            });
+        }
+    } else {
+        let since = (Utc::now() - Duration::days(days)).timestamp_millis();
+        for (user_id, accounts) in members {
+            let mut totals = PerformanceTotals::default();
+            for account in accounts.iter() {
+                for match_dto in store.cached_matches(&account.puuid, since).await {
+                    if let Some(me) = find_participant(&match_dto, &account.puuid) {
+                        totals.add(me, &match_dto.info);
+                    }
+                }
+            }
+
+            let summary = format!(
+                "{} games - {:.0}% WR - {:.2} KDA",
+                totals.games,
+                totals.winrate(),
+                totals.kda()
+            );
+            let key = match metric {
+                _ if totals.games == 0 => None,
+                "winrate" => Some(totals.winrate()),
+                "kda" => Some(totals.kda()),
+                _ => Some(totals.games as f64),
+            };
+            rows.push((user_id, key, summary));
+        }
+    }
+    rows.sort_by(|(_, a, _), (_, b, _)| match (a, b) {
+        (Some(a), Some(b)) => b.partial_cmp(a).unwrap(),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    });
+
+    let page_count = rows.len().div_ceil(PAGE_SIZE);
+    let page = page.clamp(1, page_count);
+    let description = rows
+        .iter()
+        .enumerate()
+        .skip((page - 1) * PAGE_SIZE)
+        .take(PAGE_SIZE)
+        .map(|(index, (user_id, _, value))| {
+            let place = match index {
+                0 => "🥇".to_string(),
+                1 => "🥈".to_string(),
+                2 => "🥉".to_string(),
+                _ => format!("{}.", index + 1),
+            };
+            format!("{} <@{}> - {}", place, user_id, value)
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let mut buttons = vec![];
+    if page > 1 {
+        buttons.push((
+            format!("leaderboard:{}:{}:{}", metric, days, page - 1),
+            "◀ Previous".to_string(),
+        ));
+    }
+    if page < page_count {
+        buttons.push((
+            format!("leaderboard:{}:{}:{}", metric, days, page + 1),
+            "Next ▶".to_string(),
+        ));
+    }
+
+    let title = match metric {
+        "rank" => "Leaderboard - Solo Queue Rank".to_string(),
+        "winrate" => format!("Leaderboard - Winrate, last {} days", days),
+        "kda" => format!("Leaderboard - KDA, last {} days", days),
+        _ => format!("Leaderboard - Games played, last {} days", days),
+    };
+
+    Ok(DiscordOutput::new(
+        Colour::GOLD,
+        description,
+        vec![],
+        "".to_string(),
+        format!("{} ({}/{})", title, page, page_count),
+        "".to_string(),
+    )
+    .with_buttons(buttons))
+}
+
+/// Parses a `leaderboard:{metric}:{days}:{page}` button id
+pub fn parse_leaderboard_button(custom_id: &str) -> Option<(&str, i64, usize)> {
+    let mut parts = custom_id.strip_prefix("leaderboard:")?.split(':');
+    let metric = parts.next()?;
+    let days = parts.next()?.parse().ok()?;
+    let page = parts.next()?.parse().ok()?;
+    Some((metric, days, page))
+}
@@ -0,0 +1,246 @@
+use reqwest::Client;
+use serenity::all::{Colour, CommandDataOption, CommandDataOptionValue, GuildId, UserId};
+
+use crate::{
+    matches::fetch_matches,
+    shared::{
+        requests::request_for_puuid,
+        store::{LinkedAccount, Store},
+        types::{AccountInfoContext, DiscordOutput, Platform},
+        util::RateLimiter,
+    },
+};
+
+/// Recent games cached on link so /leaderboard has something to rank straight away
+const BACKFILL_MATCH_COUNT: i64 = 20;
+/// `account` choice that combines every linked account
+pub const ALL_ACCOUNTS: &str = "all";
+const MAX_AUTOCOMPLETE_CHOICES: usize = 25;
+
+/// Adds the account to the user's linked accounts, the first one linked is the primary
+#[allow(clippy::too_many_arguments)]
+pub async fn handle_link_command(
+    user_id: UserId,
+    guild_id: Option<GuildId>,
+    riot_id: &str,
+    platform: Platform,
+    primary: bool,
+    api_key: &str,
+    client: &Client,
+    store: &Store,
+    limiter: &RateLimiter,
+) -> Result<DiscordOutput, Box<dyn std::error::Error>> {
+    let (player_name, tag) = parse_riot_id(riot_id)
+        .ok_or_else(|| format!("'{}' is not a Riot ID, expected Name#Tag", riot_id))?;
+
+    limiter.acquire().await;
+
+    let puuid = request_for_puuid(player_name, tag, platform, api_key, client)
+        .await
+        .map_err(|_| format!("Could not find {}#{} on {}", player_name, tag, platform))?;
+
+    let account = LinkedAccount {
+        puuid: puuid.clone(),
+        player_name: player_name.to_string(),
+        tag: tag.to_string(),
+        platform,
+    };
+    let (is_primary, account_count) = store
+        .update(|data| {
+            let accounts = data.linked_accounts.entry(user_id.get()).or_default();
+            match accounts
+                .iter()
+                .position(|linked| linked.puuid == account.puuid)
+            {
+                // Relinking refreshes the Riot ID, which can change
+                Some(index) if !primary => accounts[index] = account,
+                Some(index) => {
+                    accounts.remove(index);
+                    accounts.insert(0, account);
+                }
+                None if primary => accounts.insert(0, account),
+                None => accounts.push(account),
+            }
+            let linked = (accounts[0].puuid == puuid, accounts.len());
+
+            if let Some(guild_id) = guild_id {
+                data.guild_settings
+                    .entry(guild_id.get())
+                    .or_default()
+                    .members
+                    .insert(user_id.get());
+            }
+            linked
+        })
+        .await?;
+
+    let account_info_context = AccountInfoContext {
+        puuid: puuid.clone(),
+        player_name: player_name.to_string(),
+        tag: tag.to_string(),
+        platform,
+    };
+    // Best effort, the announcement poller keeps the cache filled from here on
+    let _ = fetch_matches(
+        BACKFILL_MATCH_COUNT,
+        api_key,
+        account_info_context,
+        client,
+        store,
+        limiter,
+    )
+    .await;
+
+    Ok(DiscordOutput::new(
+        Colour::DARK_GREEN,
+        format!(
+            "<@{}> is now linked to {}#{} ({}){}",
+            user_id,
+            player_name,
+            tag,
+            platform.display_name(),
+            match (is_primary, account_count) {
+                (_, 1) => "".to_string(),
+                (true, count) => format!(" as their primary account, {} accounts linked", count),
+                (false, count) => format!(" as an alt account, {} accounts linked", count),
+            }
+        ),
+        vec![],
+        "".to_string(),
+        "Account linked".to_string(),
+        "".to_string(),
+    ))
+}
+
+/// Splits `Name#Tag`, trimming the whitespace people tend to paste around it
+pub fn parse_riot_id(riot_id: &str) -> Option<(&str, &str)> {
+    let (player_name, tag) = riot_id.trim().rsplit_once('#')?;
+    let (player_name, tag) = (player_name.trim(), tag.trim());
+    if player_name.is_empty() || tag.is_empty() {
+        return None;
+    }
+    Some((player_name, tag))
+}
+
+/// Position of the linked account matching `riot_id`, the tag can be left out
+pub fn find_linked_account(accounts: &[LinkedAccount], riot_id: &str) -> Option<usize> {
+    let (player_name, tag) = parse_riot_id(riot_id).unwrap_or((riot_id.trim(), ""));
+    accounts.iter().position(|account| {
+        account.player_name.eq_ignore_ascii_case(player_name)
+            && (tag.is_empty() || account.tag.eq_ignore_ascii_case(tag))
+    })
+}
+
+/// `/accounts list|primary|remove` for the caller's own linked accounts
+pub async fn handle_accounts_command(
+    user_id: UserId,
+    subcommand: &CommandDataOption,
+    store: &Store,
+) -> Result<DiscordOutput, Box<dyn std::error::Error>> {
+    let riot_id = match &subcommand.value {
+        CommandDataOptionValue::SubCommand(options) => options
+            .iter()
+            .find(|opt| opt.name == "riot_id")
+            .and_then(|opt| opt.value.as_str()),
+        _ => return Err("Expected a subcommand".into()),
+    };
+
+    let message = match (subcommand.name.as_str(), riot_id) {
+        ("primary", Some(riot_id)) => {
+            store
+                .update(|data| {
+                    let accounts = data
+                        .linked_accounts
+                        .get_mut(&user_id.get())
+                        .ok_or("You have no linked accounts, use /link")?;
+                    let index = find_linked_account(accounts, riot_id)
+                        .ok_or_else(|| format!("{} is not one of your linked accounts", riot_id))?;
+                    let account = accounts.remove(index);
+                    let message = format!("{} is now your primary account", account.riot_id());
+                    accounts.insert(0, account);
+                    Ok::<String, String>(message)
+                })
+                .await??
+        }
+        ("remove", Some(riot_id)) => {
+            store
+                .update(|data| {
+                    let accounts = data
+                        .linked_accounts
+                        .get_mut(&user_id.get())
+                        .ok_or("You have no linked accounts, use /link")?;
+                    let index = find_linked_account(accounts, riot_id)
+                        .ok_or_else(|| format!("{} is not one of your linked accounts", riot_id))?;
+                    let account = accounts.remove(index);
+                    let message = match accounts.first() {
+                        Some(primary) if index == 0 => format!(
+                            "Unlinked {}, {} is now your primary account",
+                            account.riot_id(),
+                            primary.riot_id()
+                        ),
+                        _ => format!("Unlinked {}", account.riot_id()),
+                    };
+                    if accounts.is_empty() {
+                        data.linked_accounts.remove(&user_id.get());
+                    }
+                    Ok::<String, String>(message)
+                })
+                .await??
+        }
+        _ => {
+            let data = store.read().await;
+            let accounts = data
+                .linked_accounts
+                .get(&user_id.get())
+                .filter(|accounts| !accounts.is_empty())
+                .ok_or("You have no linked accounts, use /link")?;
+            accounts
+                .iter()
+                .enumerate()
+                .map(|(index, account)| {
+                    format!(
+                        "{} {} ({}){}",
+                        if index == 0 { "⭐" } else { "▫️" },
+                        account.riot_id(),
+                        account.platform.display_name(),
+                        if index == 0 { " - primary" } else { "" }
+                    )
+                })
+                .collect::<Vec<String>>()
+                .join("\n")
+        }
+    };
+
+    Ok(DiscordOutput::new(
+        Colour::DARK_GREEN,
+        message,
+        vec![],
+        "".to_string(),
+        "Linked Accounts".to_string(),
+        "".to_string(),
+    ))
+}
+
+/// Riot IDs of the user's linked accounts starting with what was typed, primary first
+pub async fn autocomplete_linked_accounts(
+    user_id: UserId,
+    typed: &str,
+    include_all: bool,
+    store: &Store,
+) -> Vec<String> {
+    let typed = typed.trim().to_lowercase();
+    let data = store.read().await;
+    let accounts = data
+        .linked_accounts
+        .get(&user_id.get())
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+
+    let mut choices: Vec<String> = accounts.iter().map(LinkedAccount::riot_id).collect();
+    if include_all && accounts.len() > 1 {
+        choices.push(ALL_ACCOUNTS.to_string());
+    }
+    choices.retain(|choice| choice.to_lowercase().starts_with(&typed));
+    choices.truncate(MAX_AUTOCOMPLETE_CHOICES);
+    choices
+}
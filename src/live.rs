@@ -0,0 +1,185 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use futures::future::join_all;
+use reqwest::Client;
+use serenity::all::Colour;
+
+use crate::shared::{
+    requests::{request_active_game, request_for_puuid, request_league_entries},
+    static_data::{champions, queue_name, summoner_spells},
+    types::{solo_queue_entry, CurrentGameInfo, CurrentGameParticipant, DiscordOutput, Platform},
+    util::RateLimiter,
+};
+
+const BLUE_TEAM_ID: u32 = 100;
+
+pub async fn handle_live_command(
+    player_name: &str,
+    tag: &str,
+    platform: Platform,
+    api_key: &str,
+    client: &Client,
+    limiter: &RateLimiter,
+) -> Result<DiscordOutput, Box<dyn std::error::Error>> {
+    limiter.acquire().await;
+    let puuid = request_for_puuid(player_name, tag, platform, api_key, client).await?;
+
+    limiter.acquire().await;
+    let game = match request_active_game(&puuid, platform, api_key, client).await? {
+        Some(game) => game,
+        None => {
+            return Ok(DiscordOutput::new(
+                Colour::LIGHT_GREY,
+                format!("{}#{} is not in a game right now", player_name, tag),
+                vec![],
+                "".to_string(),
+                format!("{}#{}", player_name, tag),
+                "".to_string(),
+            ));
+        }
+    };
+
+    get_live_game_info(
+        &game,
+        &puuid,
+        player_name,
+        tag,
+        platform,
+        api_key,
+        client,
+        limiter,
+    )
+    .await
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn get_live_game_info(
+    game: &CurrentGameInfo,
+    puuid: &str,
+    player_name: &str,
+    tag: &str,
+    platform: Platform,
+    api_key: &str,
+    client: &Client,
+    limiter: &RateLimiter,
+) -> Result<DiscordOutput, Box<dyn std::error::Error>> {
+    let champions = champions(client).await?;
+    let summoner_spells = summoner_spells(client).await?;
+
+    let rank_futures = game.participants.iter().map(|participant| async move {
+        let puuid = participant.puuid.as_deref()?;
+        limiter.acquire().await;
+        let entries = request_league_entries(puuid, platform, api_key, client)
+            .await
+            .ok()?;
+        solo_queue_entry(&entries).map(|entry| entry.rank_string())
+    });
+    let ranks = join_all(rank_futures).await;
+
+    let champion_name = |participant: &CurrentGameParticipant| {
+        champions
+            .get(&participant.champion_id)
+            .map(|champion| champion.name.clone())
+            .unwrap_or_else(|| "Unknown".to_string())
+    };
+    let spell_name = |spell_id: i64| {
+        summoner_spells
+            .get(&spell_id)
+            .cloned()
+            .unwrap_or_else(|| "?".to_string())
+    };
+
+    let team_lines = |team_id: u32| {
+        game.participants
+            .iter()
+            .zip(ranks.iter())
+            .filter(|(participant, _)| participant.team_id == team_id)
+            .map(|(participant, rank)| {
+                let marker = if participant.puuid.as_deref() == Some(puuid) {
+                    ">"
+                } else {
+                    " "
+                };
+                format!(
+                    "{}{:<13}{:<16}{}\n  {}",
+                    marker,
+                    champion_name(participant),
+                    format!(
+                        "{}/{}",
+                        spell_name(participant.spell1_id),
+                        spell_name(participant.spell2_id)
+                    ),
+                    rank.as_deref().unwrap_or("Unranked"),
+                    participant.riot_id
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    };
+
+    let fields = vec![
+        (
+            "🔵 - Blue Team".to_string(),
+            format!("```{}```", team_lines(BLUE_TEAM_ID)),
+            false,
+        ),
+        (
+            "🔴 - Red Team".to_string(),
+            format!("```{}```", team_lines(BLUE_TEAM_ID + 100)),
+            false,
+        ),
+    ];
+
+    let queue = game
+        .game_queue_config_id
+        .map(queue_name)
+        .unwrap_or(game.game_mode.as_str());
+
+    // Spectator data has no positions and pick slots don't follow lanes, so every enemy
+    // gets a matchup button and the player picks their lane opponent
+    let mut buttons = vec![];
+    let me = game
+        .participants
+        .iter()
+        .find(|p| p.puuid.as_deref() == Some(puuid));
+    let my_champion = me.and_then(|me| champions.get(&me.champion_id));
+    if let (Some(me), Some(my_champion)) = (me, my_champion) {
+        for enemy in game.participants.iter().filter(|p| p.team_id != me.team_id) {
+            if let Some(enemy) = champions.get(&enemy.champion_id) {
+                buttons.push((
+                    format!("build:{}:{}", my_champion.u_gg_name(), enemy.u_gg_name()),
+                    format!("vs. {}", enemy.name),
+                ));
+            }
+        }
+    }
+
+    Ok(DiscordOutput::new(
+        Colour::BLURPLE,
+        format!("{} - {}", queue, format_elapsed(game)),
+        fields,
+        "".to_string(),
+        format!("{}#{} is in game", player_name, tag),
+        "".to_string(),
+    )
+    .with_buttons(buttons))
+}
+
+fn format_elapsed(game: &CurrentGameInfo) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as i64)
+        .unwrap_or(0);
+
+    let elapsed_seconds = if game.game_start_time > 0 {
+        (now - game.game_start_time) / 1000
+    } else {
+        game.game_length
+    };
+
+    if elapsed_seconds <= 0 {
+        return "Loading in".to_string();
+    }
+
+    format!("{}:{:02}", elapsed_seconds / 60, elapsed_seconds % 60)
+}
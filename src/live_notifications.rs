@@ -0,0 +1,263 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+
+use reqwest::Client;
+use serenity::all::{ChannelId, Colour, CreateEmbed, CreateMessage, EditMessage, Http, MessageId};
+use tokio::time::sleep;
+use tracing::warn;
+
+use crate::shared::{
+    requests::request_active_game,
+    static_data::{champions, queue_name},
+    store::{LinkedAccount, Store},
+    types::{CurrentGameInfo, DiscordOutput},
+    util::RateLimiter,
+};
+
+/// With many linked players a round simply takes longer instead of getting the key rate
+/// limited
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+const BLUE_TEAM_ID: u32 = 100;
+
+type PollError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Background task started from `ready()`, posts a status message when linked players enter
+/// a game in guilds that turned on live games, and edits it once the game is over
+pub async fn poll_live_games(
+    http: Arc<Http>,
+    client: Client,
+    api_key: String,
+    store: Arc<Store>,
+    limiter: Arc<RateLimiter>,
+) {
+    loop {
+        if let Err(err) = announce_live_games(&http, &client, &api_key, &store, &limiter).await {
+            warn!("Polling live games failed: {}", err);
+        }
+        sleep(POLL_INTERVAL).await;
+    }
+}
+
+async fn announce_live_games(
+    http: &Http,
+    client: &Client,
+    api_key: &str,
+    store: &Store,
+    limiter: &RateLimiter,
+) -> Result<(), PollError> {
+    let guilds = {
+        let data = store.read().await;
+        data.guild_settings
+            .iter()
+            .filter(|(_, settings)| settings.live_games)
+            .filter_map(|(guild_id, settings)| {
+                Some((
+                    *guild_id,
+                    ChannelId::new(settings.announcement_channel?),
+                    data.guild_accounts(*guild_id)
+                        .into_iter()
+                        .map(|(_, account)| account.clone())
+                        .collect::<Vec<LinkedAccount>>(),
+                ))
+            })
+            .collect::<Vec<_>>()
+    };
+
+    // Each account is looked up once per round, however many guilds it is linked in
+    let mut accounts: HashMap<String, LinkedAccount> = HashMap::new();
+    for (_, _, guild_accounts) in guilds.iter() {
+        for account in guild_accounts {
+            accounts.insert(account.puuid.clone(), account.clone());
+        }
+    }
+
+    let mut games: HashMap<i64, CurrentGameInfo> = HashMap::new();
+    let mut failed_lookups = false;
+    for account in accounts.values() {
+        if games.values().any(|game| is_in_game(game, &account.puuid)) {
+            continue;
+        }
+        limiter.acquire_background().await;
+        match request_active_game(&account.puuid, account.platform, api_key, client).await {
+            Ok(Some(game)) => {
+                games.insert(game.game_id, game);
+            }
+            Ok(None) => {}
+            Err(err) => {
+                warn!(
+                    "Could not check if {}#{} is in game: {}",
+                    account.player_name, account.tag, err
+                );
+                failed_lookups = true;
+            }
+        }
+    }
+
+    for (guild_id, channel, guild_accounts) in guilds {
+        let posted = store.read().await.guild_settings[&guild_id]
+            .live_messages
+            .clone();
+
+        for game in games.values() {
+            if posted.contains_key(&game.game_id) {
+                continue;
+            }
+            let players: Vec<&LinkedAccount> = guild_accounts
+                .iter()
+                .filter(|account| is_in_game(game, &account.puuid))
+                .collect();
+            if players.is_empty() {
+                continue;
+            }
+
+            let output = get_live_game_status(game, &players, client, store).await?;
+            let message = CreateMessage::new()
+                .embed(output.to_embed())
+                .components(output.to_components());
+            match channel.send_message(http, message).await {
+                Ok(message) => {
+                    store
+                        .update(|data| {
+                            data.guild_settings
+                                .entry(guild_id)
+                                .or_default()
+                                .live_messages
+                                .insert(game.game_id, message.id.get())
+                        })
+                        .await?;
+                }
+                Err(err) => warn!("Could not post live game in {}: {}", channel, err),
+            }
+        }
+
+        // A failed lookup could hide a game that is still going, so only close games on
+        // rounds where every player was checked
+        if failed_lookups {
+            continue;
+        }
+        let finished: HashSet<i64> = posted
+            .keys()
+            .filter(|game_id| !games.contains_key(game_id))
+            .copied()
+            .collect();
+        for game_id in finished.iter() {
+            let message_id = MessageId::new(posted[game_id]);
+            if let Err(err) = close_live_game_message(http, channel, message_id).await {
+                warn!("Could not close live game message in {}: {}", channel, err);
+            }
+        }
+        if !finished.is_empty() {
+            store
+                .update(|data| {
+                    data.guild_settings
+                        .entry(guild_id)
+                        .or_default()
+                        .live_messages
+                        .retain(|game_id, _| !finished.contains(game_id))
+                })
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+fn is_in_game(game: &CurrentGameInfo, puuid: &str) -> bool {
+    game.participants
+        .iter()
+        .any(|participant| participant.puuid.as_deref() == Some(puuid))
+}
+
+/// Ranks come from the store so a new game doesn't cost ten league calls
+async fn get_live_game_status(
+    game: &CurrentGameInfo,
+    players: &[&LinkedAccount],
+    client: &Client,
+    store: &Store,
+) -> Result<DiscordOutput, PollError> {
+    let champions = champions(client).await?;
+    let data = store.read().await;
+
+    let team_lines = |team_id: u32| {
+        game.participants
+            .iter()
+            .filter(|participant| participant.team_id == team_id)
+            .map(|participant| {
+                let champion = champions
+                    .get(&participant.champion_id)
+                    .map(|champion| champion.name.as_str())
+                    .unwrap_or("Unknown");
+                let linked = participant
+                    .puuid
+                    .as_deref()
+                    .filter(|puuid| players.iter().any(|player| player.puuid == *puuid));
+                match linked {
+                    Some(puuid) => format!(
+                        "**{}** - {} ({})",
+                        champion,
+                        participant.riot_id,
+                        data.latest_solo_rank(puuid)
+                            .map(|entry| entry.rank_string())
+                            .unwrap_or_else(|| "Unranked".to_string())
+                    ),
+                    None => format!("{} - {}", champion, participant.riot_id),
+                }
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    };
+
+    let fields = vec![
+        ("🔵 - Blue Team".to_string(), team_lines(BLUE_TEAM_ID), true),
+        (
+            "🔴 - Red Team".to_string(),
+            team_lines(BLUE_TEAM_ID + 100),
+            true,
+        ),
+    ];
+
+    let names = players
+        .iter()
+        .map(|player| format!("{}#{}", player.player_name, player.tag))
+        .collect::<Vec<String>>()
+        .join(", ");
+    let queue = game
+        .game_queue_config_id
+        .map(queue_name)
+        .unwrap_or(game.game_mode.as_str());
+
+    Ok(DiscordOutput::new(
+        Colour::BLURPLE,
+        queue.to_string(),
+        fields,
+        "".to_string(),
+        format!("🎮 {} started a game", names),
+        "".to_string(),
+    )
+    .with_buttons(vec![(
+        format!("live:{}", players[0].puuid),
+        "Live view".to_string(),
+    )]))
+}
+
+/// Keeps the teams on the status message, greys it out and drops the live view button
+async fn close_live_game_message(
+    http: &Http,
+    channel: ChannelId,
+    message_id: MessageId,
+) -> Result<(), serenity::Error> {
+    let message = channel.message(http, message_id).await?;
+    let mut edit = EditMessage::new().components(vec![]);
+    if let Some(embed) = message.embeds.into_iter().next() {
+        edit = edit.embed(
+            CreateEmbed::from(embed)
+                .colour(Colour::LIGHT_GREY)
+                .description("🏁 This game has finished"),
+        );
+    }
+    channel.edit_message(http, message_id, edit).await?;
+    Ok(())
+}
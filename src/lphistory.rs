@@ -0,0 +1,143 @@
+use chrono::{DateTime, Duration, Utc};
+use plotters::prelude::*;
+use reqwest::Client;
+use serenity::all::Colour;
+
+use crate::shared::{
+    charts::{encode_png, register_fonts, CHART_BACKGROUND, CHART_HEIGHT, CHART_WIDTH},
+    requests::{request_for_puuid, request_league_entries},
+    store::Store,
+    types::{solo_queue_entry, tier_label, DiscordOutput, Platform},
+    util::RateLimiter,
+};
+
+const CHART_LINE: RGBColor = RGBColor(88, 101, 242);
+
+#[allow(clippy::too_many_arguments)]
+pub async fn handle_lphistory_command(
+    player_name: &str,
+    tag: &str,
+    platform: Platform,
+    days: i64,
+    api_key: &str,
+    client: &Client,
+    store: &Store,
+    limiter: &RateLimiter,
+) -> Result<DiscordOutput, Box<dyn std::error::Error>> {
+    limiter.acquire().await;
+    let puuid = request_for_puuid(player_name, tag, platform, api_key, client).await?;
+
+    // Asking for a chart also counts as a snapshot, so unlinked players build history too
+    limiter.acquire().await;
+    let entries = request_league_entries(&puuid, platform, api_key, client).await?;
+    let now = Utc::now();
+    if let Some(entry) = solo_queue_entry(&entries) {
+        store
+            .update(|data| data.record_rank_snapshot(&puuid, now.timestamp(), entry))
+            .await?;
+    }
+
+    let since = (now - Duration::days(days)).timestamp();
+    let history: Vec<(DateTime<Utc>, i32)> = store
+        .read()
+        .await
+        .rank_history
+        .get(&puuid)
+        .into_iter()
+        .flatten()
+        .filter(|snapshot| snapshot.timestamp >= since)
+        .filter_map(|snapshot| {
+            Some((
+                DateTime::from_timestamp(snapshot.timestamp, 0)?,
+                snapshot.entry.absolute_league_points(),
+            ))
+        })
+        .collect();
+
+    let title = format!("{}#{}'s LP History", player_name, tag);
+    if history.len() < 2 {
+        return Ok(DiscordOutput::new(
+            Colour::LIGHT_GREY,
+            "Not enough ranked snapshots yet, linked players are snapshotted every 10 minutes"
+                .to_string(),
+            vec![],
+            "".to_string(),
+            title,
+            "".to_string(),
+        ));
+    }
+
+    let first = history.first().unwrap().1;
+    let last = history.last().unwrap().1;
+    let chart = draw_lp_chart(&history)?;
+
+    Ok(DiscordOutput::new(
+        if last >= first {
+            Colour::DARK_GREEN
+        } else {
+            Colour::DARK_RED
+        },
+        format!(
+            "{} - {:+} LP over the last {} days",
+            solo_queue_entry(&entries)
+                .map(|entry| entry.rank_string())
+                .unwrap_or_else(|| "Unranked".to_string()),
+            last - first,
+            days
+        ),
+        vec![],
+        "".to_string(),
+        title,
+        "".to_string(),
+    )
+    .with_attachment("lphistory.png".to_string(), chart))
+}
+
+fn draw_lp_chart(history: &[(DateTime<Utc>, i32)]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    register_fonts();
+
+    let start = history.first().unwrap().0;
+    let end = history.last().unwrap().0;
+    // Pad to whole divisions so every gridline lands on a tier label
+    let min_lp = history.iter().map(|(_, lp)| *lp).min().unwrap() / 100 * 100;
+    let max_lp = (history.iter().map(|(_, lp)| *lp).max().unwrap() / 100 + 1) * 100;
+
+    let mut buffer = vec![0; (CHART_WIDTH * CHART_HEIGHT * 3) as usize];
+    {
+        let root = BitMapBackend::with_buffer(&mut buffer, (CHART_WIDTH, CHART_HEIGHT))
+            .into_drawing_area();
+        root.fill(&CHART_BACKGROUND)?;
+
+        let mut chart = ChartBuilder::on(&root)
+            .margin(20)
+            .x_label_area_size(30)
+            .y_label_area_size(100)
+            .build_cartesian_2d(start..end, min_lp..max_lp)?;
+
+        chart
+            .configure_mesh()
+            .x_labels(6)
+            .x_label_formatter(&|date| date.format("%b %d").to_string())
+            .y_labels(((max_lp - min_lp) / 100 + 1) as usize)
+            .y_label_formatter(&|lp| tier_label(*lp))
+            .label_style(("sans-serif", 14).into_font().color(&WHITE))
+            .axis_style(WHITE.mix(0.5))
+            .bold_line_style(WHITE.mix(0.15))
+            .light_line_style(TRANSPARENT)
+            .draw()?;
+
+        chart.draw_series(LineSeries::new(
+            history.iter().copied(),
+            CHART_LINE.stroke_width(3),
+        ))?;
+        chart.draw_series(
+            history
+                .iter()
+                .map(|point| Circle::new(*point, 3, CHART_LINE.filled())),
+        )?;
+
+        root.present()?;
+    }
+
+    encode_png(buffer, CHART_WIDTH, CHART_HEIGHT)
+}
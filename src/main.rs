@@ -1,69 +1,178 @@
-use std::time::Instant;
+use std::{
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 
 use anyhow::Context as _;
 use headless_chrome::{Browser, LaunchOptionsBuilder};
+use link::ALL_ACCOUNTS;
 use serenity::all::*;
-use shared::types::DiscordOutput;
+use shared::{
+    store::{LinkedAccount, Store},
+    types::{DiscordOutput, Platform},
+    util::RateLimiter,
+};
 use shuttle_runtime::SecretStore;
+use strum::IntoEnumIterator;
 use tracing::info;
 
+mod announcements;
 mod build;
+mod coach;
+mod compare;
 mod counters;
+mod duos;
+mod export;
+mod heatmap;
+mod leaderboard;
+mod link;
+mod live;
+mod live_notifications;
+mod lphistory;
+mod mastery;
 pub mod matches;
+mod performance;
+mod playtime;
+mod rank_alerts;
+mod recap;
+mod scout;
+mod season;
+mod settings;
 pub mod shared;
+mod shortcuts;
+mod stats;
 pub mod tierlist;
+mod tilt;
+mod timeline;
+
+/// Riot dev keys allow 20 requests a second and 100 every 2 minutes
+const RIOT_REQUESTS_PER_SECOND: usize = 20;
+const RIOT_REQUESTS_PER_TWO_MINUTES: usize = 100;
 
 struct Bot {
     client: reqwest::Client,
     discord_guild_id: GuildId,
     riot_api_key: String,
+    store: Arc<Store>,
+    /// `ready` fires again on reconnect, background tasks must only start once
+    tasks_started: AtomicBool,
+    /// Shared by every poller and the commands that fan out into many Riot calls, like /scout
+    riot_limiter: Arc<RateLimiter>,
+}
+
+impl Bot {
+    /// Resolves which Riot account a command is about: an explicit name and tag first, then
+    /// the mentioned user's linked account, then the caller's own
+    async fn resolve_account(
+        &self,
+        command: &CommandInteraction,
+    ) -> Result<(String, String, Platform), String> {
+        let iter = command.data.options.iter();
+
+        let player_name = iter
+            .clone()
+            .find(|opt| opt.name == "player_name")
+            .and_then(|opt| opt.value.as_str());
+        let tag = iter
+            .clone()
+            .find(|opt| opt.name == "tag")
+            .and_then(|opt| opt.value.as_str());
+        let platform = iter
+            .clone()
+            .find(|opt| opt.name == "region")
+            .and_then(|opt| opt.value.as_str())
+            .and_then(|region| Platform::from_str(region).ok());
+
+        if let Some(player_name) = player_name {
+            let (player_name, tag) = match tag {
+                Some(tag) => (player_name, tag),
+                None => link::parse_riot_id(player_name)
+                    .ok_or_else(|| format!("No tag given for {}", player_name))?,
+            };
+            return Ok((
+                player_name.to_string(),
+                tag.to_string(),
+                platform.unwrap_or_default(),
+            ));
+        }
+
+        let user_id = iter
+            .clone()
+            .find(|opt| opt.name == "user")
+            .and_then(|opt| opt.value.as_user_id())
+            .unwrap_or(command.user.id);
+
+        let store = self.store.read().await;
+        let primary = store.primary_account(user_id.get()).ok_or_else(|| {
+            format!(
+                "<@{}> has no linked account, use /link or pass player_name and tag",
+                user_id
+            )
+        })?;
+        let account = match account_option(command) {
+            None => primary,
+            Some(ALL_ACCOUNTS) => {
+                return Err("Only /stats can combine all accounts, pick one".to_string())
+            }
+            Some(riot_id) => {
+                let accounts = &store.linked_accounts[&user_id.get()];
+                link::find_linked_account(accounts, riot_id)
+                    .map(|index| &accounts[index])
+                    .ok_or_else(|| format!("<@{}> has no linked account {}", user_id, riot_id))?
+            }
+        };
+
+        Ok((
+            account.player_name.clone(),
+            account.tag.clone(),
+            platform.unwrap_or(account.platform),
+        ))
+    }
+
+    /// Every account linked by the mentioned user or the caller, for `account:all`
+    async fn resolve_all_accounts(
+        &self,
+        command: &CommandInteraction,
+    ) -> Result<Vec<LinkedAccount>, String> {
+        let user_id = command
+            .data
+            .options
+            .iter()
+            .find(|opt| opt.name == "user")
+            .and_then(|opt| opt.value.as_user_id())
+            .unwrap_or(command.user.id);
+
+        self.store
+            .read()
+            .await
+            .linked_accounts
+            .get(&user_id.get())
+            .filter(|accounts| !accounts.is_empty())
+            .cloned()
+            .ok_or_else(|| format!("<@{}> has no linked account, use /link", user_id))
+    }
 }
 
 #[async_trait]
 impl EventHandler for Bot {
     async fn ready(&self, ctx: Context, ready: Ready) {
         info!("{} is connected!", ready.user.name);
-        let matches = CreateCommand::new("matches")
-            .description("Get match info for player")
-            .add_option(
-                CreateCommandOption::new(
-                    serenity::all::CommandOptionType::String,
-                    "player_name",
-                    "Player Name",
-                )
-                .required(true),
-            )
-            .add_option(
-                CreateCommandOption::new(
-                    serenity::all::CommandOptionType::String,
-                    "tag",
-                    "playerTag",
-                )
-                .required(true),
-            )
-            .add_option(
-                CreateCommandOption::new(
-                    serenity::all::CommandOptionType::String,
-                    "region",
-                    "Region",
-                )
-                .add_string_choice("Americas", "americas")
-                .add_string_choice("Asia", "asia")
-                .add_string_choice("Europe", "europe")
-                .required(false),
-            )
-            .add_option(
-                CreateCommandOption::new(
-                    serenity::all::CommandOptionType::Integer,
-                    "game_count",
-                    "Number of games to check",
-                )
-                .min_int_value(0)
-                .max_int_value(40)
-                .required(false),
-            );
-
-        let john = CreateCommand::new("john").description("Look at this guy");
+        let matches =
+            player_options(CreateCommand::new("matches").description("Get match info for player"))
+                .add_option(
+                    CreateCommandOption::new(
+                        serenity::all::CommandOptionType::Integer,
+                        "game_count",
+                        "Number of games to check",
+                    )
+                    .min_int_value(0)
+                    .max_int_value(40)
+                    .required(false),
+                );
 
         let build = CreateCommand::new("build")
             .description("Get build data")
@@ -143,7 +252,498 @@ impl EventHandler for Bot {
                 .required(false),
             );
 
-        let commands = vec![matches, john, build, counter, tierlist];
+        let live =
+            player_options(CreateCommand::new("live").description("Get live game info for player"));
+
+        let stats = player_options(
+            CreateCommand::new("stats")
+                .description("Get per champion and per role stats for player"),
+        )
+        .add_option(
+            CreateCommandOption::new(
+                serenity::all::CommandOptionType::Integer,
+                "game_count",
+                "Number of games to check",
+            )
+            .min_int_value(1)
+            .max_int_value(100)
+            .required(false),
+        );
+
+        let link = CreateCommand::new("link")
+            .description("Link your Discord account to a Riot account")
+            .add_option(
+                CreateCommandOption::new(
+                    serenity::all::CommandOptionType::String,
+                    "riot_id",
+                    "Riot ID, e.g. Name#Tag",
+                )
+                .required(true),
+            )
+            .add_option(region_option().required(true))
+            .add_option(
+                CreateCommandOption::new(
+                    serenity::all::CommandOptionType::Boolean,
+                    "primary",
+                    "Make this your primary account, the first one linked already is",
+                )
+                .required(false),
+            );
+
+        let riot_id_option = CreateCommandOption::new(
+            serenity::all::CommandOptionType::String,
+            "riot_id",
+            "One of your linked Riot IDs",
+        )
+        .set_autocomplete(true)
+        .required(true);
+        let accounts = CreateCommand::new("accounts")
+            .description("Manage your linked Riot accounts")
+            .add_option(CreateCommandOption::new(
+                serenity::all::CommandOptionType::SubCommand,
+                "list",
+                "List your linked accounts",
+            ))
+            .add_option(
+                CreateCommandOption::new(
+                    serenity::all::CommandOptionType::SubCommand,
+                    "primary",
+                    "Pick the account commands use by default",
+                )
+                .add_sub_option(riot_id_option.clone()),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    serenity::all::CommandOptionType::SubCommand,
+                    "remove",
+                    "Unlink one of your accounts",
+                )
+                .add_sub_option(riot_id_option),
+            );
+
+        let shortcut_command_option = shortcuts::SHORTCUT_COMMANDS.iter().fold(
+            CreateCommandOption::new(
+                serenity::all::CommandOptionType::String,
+                "command",
+                "Command to run",
+            )
+            .required(true),
+            |option, command| option.add_string_choice(*command, *command),
+        );
+        let shortcut = CreateCommand::new("shortcut")
+            .description("Manage this server's /run shortcuts")
+            .add_option(
+                CreateCommandOption::new(
+                    serenity::all::CommandOptionType::SubCommand,
+                    "add",
+                    "Save a player command under a name",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        serenity::all::CommandOptionType::String,
+                        "name",
+                        "Shortcut name",
+                    )
+                    .required(true),
+                )
+                .add_sub_option(shortcut_command_option)
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        serenity::all::CommandOptionType::String,
+                        "player",
+                        "Player Name",
+                    )
+                    .required(true),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        serenity::all::CommandOptionType::String,
+                        "tag",
+                        "playerTag",
+                    )
+                    .required(true),
+                )
+                .add_sub_option(region_option())
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        serenity::all::CommandOptionType::Integer,
+                        "game_count",
+                        "Number of games to check",
+                    )
+                    .min_int_value(1)
+                    .max_int_value(40)
+                    .required(false),
+                ),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    serenity::all::CommandOptionType::SubCommand,
+                    "remove",
+                    "Delete a shortcut",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        serenity::all::CommandOptionType::String,
+                        "name",
+                        "Shortcut name",
+                    )
+                    .set_autocomplete(true)
+                    .required(true),
+                ),
+            )
+            .add_option(CreateCommandOption::new(
+                serenity::all::CommandOptionType::SubCommand,
+                "list",
+                "List this server's shortcuts",
+            ));
+
+        let run = CreateCommand::new("run")
+            .description("Run a saved shortcut")
+            .add_option(
+                CreateCommandOption::new(
+                    serenity::all::CommandOptionType::String,
+                    "name",
+                    "Shortcut name",
+                )
+                .set_autocomplete(true)
+                .required(true),
+            );
+
+        // Server-wide channels and alerts, so only members who can manage the server see it
+        let settings = CreateCommand::new("settings")
+            .description("Configure the bot for this server")
+            .default_member_permissions(Permissions::MANAGE_GUILD)
+            .add_option(
+                CreateCommandOption::new(
+                    serenity::all::CommandOptionType::SubCommand,
+                    "announcements",
+                    "Post finished games of linked players in a channel",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        serenity::all::CommandOptionType::Channel,
+                        "channel",
+                        "Channel to post in, leave empty to turn off",
+                    )
+                    .channel_types(vec![ChannelType::Text])
+                    .required(false),
+                ),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    serenity::all::CommandOptionType::SubCommand,
+                    "recap",
+                    "Post a recap of the past week every Monday",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        serenity::all::CommandOptionType::Channel,
+                        "channel",
+                        "Channel to post in, leave empty to turn off",
+                    )
+                    .channel_types(vec![ChannelType::Text])
+                    .required(false),
+                ),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    serenity::all::CommandOptionType::SubCommand,
+                    "live_games",
+                    "Post in the announcement channel when linked players start a game",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        serenity::all::CommandOptionType::Boolean,
+                        "enabled",
+                        "Turn live game notifications on or off",
+                    )
+                    .required(true),
+                ),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    serenity::all::CommandOptionType::SubCommand,
+                    "rank_alerts",
+                    "Post promotions, demotions and big LP swings in the announcement channel",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        serenity::all::CommandOptionType::Boolean,
+                        "enabled",
+                        "Turn rank alerts on or off",
+                    )
+                    .required(true),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        serenity::all::CommandOptionType::Integer,
+                        "lp_swing",
+                        "LP gained or lost before posting",
+                    )
+                    .min_int_value(10)
+                    .max_int_value(500)
+                    .required(false),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        serenity::all::CommandOptionType::Boolean,
+                        "divisions",
+                        "Post tier and division changes",
+                    )
+                    .required(false),
+                ),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    serenity::all::CommandOptionType::SubCommand,
+                    "tilt_alerts",
+                    "Suggest a break in the announcement channel after a ranked losing streak",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        serenity::all::CommandOptionType::Boolean,
+                        "enabled",
+                        "Turn tilt alerts on or off",
+                    )
+                    .required(true),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        serenity::all::CommandOptionType::Integer,
+                        "losses",
+                        "Ranked losses in a row before suggesting a break",
+                    )
+                    .min_int_value(2)
+                    .max_int_value(10)
+                    .required(false),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        serenity::all::CommandOptionType::Integer,
+                        "hours",
+                        "Window the losses have to fall within",
+                    )
+                    .min_int_value(1)
+                    .max_int_value(24)
+                    .required(false),
+                ),
+            );
+
+        let lphistory = player_options(
+            CreateCommand::new("lphistory").description("Chart a player's solo queue LP over time"),
+        )
+        .add_option(
+            CreateCommandOption::new(
+                serenity::all::CommandOptionType::Integer,
+                "days",
+                "Number of days to chart",
+            )
+            .min_int_value(1)
+            .max_int_value(365)
+            .required(false),
+        );
+
+        let leaderboard = CreateCommand::new("leaderboard")
+            .description("Rank the server's linked players")
+            .add_option(
+                leaderboard::LEADERBOARD_METRICS.into_iter().fold(
+                    CreateCommandOption::new(
+                        serenity::all::CommandOptionType::String,
+                        "metric",
+                        "What to rank by, defaults to solo queue rank",
+                    )
+                    .required(false),
+                    |option, metric| option.add_string_choice(metric, metric),
+                ),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    serenity::all::CommandOptionType::Integer,
+                    "days",
+                    "Window for winrate, KDA and games",
+                )
+                .min_int_value(1)
+                .max_int_value(90)
+                .required(false),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    serenity::all::CommandOptionType::Integer,
+                    "page",
+                    "Page to start on",
+                )
+                .min_int_value(1)
+                .required(false),
+            );
+
+        let export = player_options(
+            CreateCommand::new("export").description("Export match history as a CSV or JSON file"),
+        )
+        .add_option(
+            CreateCommandOption::new(
+                serenity::all::CommandOptionType::Integer,
+                "game_count",
+                "Number of games to export",
+            )
+            .min_int_value(1)
+            .max_int_value(100)
+            .required(false),
+        )
+        .add_option(
+            export::EXPORT_FORMATS.into_iter().fold(
+                CreateCommandOption::new(
+                    serenity::all::CommandOptionType::String,
+                    "format",
+                    "File format, defaults to CSV",
+                )
+                .required(false),
+                |option, format| option.add_string_choice(format, format),
+            ),
+        );
+
+        let mastery = player_options(
+            CreateCommand::new("mastery").description("Get a player's champion mastery"),
+        )
+        .add_option(
+            CreateCommandOption::new(
+                serenity::all::CommandOptionType::String,
+                "champion",
+                "Show one champion with the player's recent record on it",
+            )
+            .required(false),
+        );
+
+        let scout = CreateCommand::new("scout")
+            .description("Scout up to 5 players pasted from the lobby or champ select")
+            .add_option(
+                CreateCommandOption::new(
+                    serenity::all::CommandOptionType::String,
+                    "text",
+                    "Pasted `Name#Tag joined the lobby` lines or comma separated Riot IDs",
+                )
+                .required(true),
+            )
+            .add_option(region_option());
+
+        // Required options have to come before the optional player ones
+        let timeline = player_options(
+            CreateCommand::new("timeline")
+                .description("Gold graph, objectives and lane leads for one match")
+                .add_option(
+                    CreateCommandOption::new(
+                        serenity::all::CommandOptionType::String,
+                        "match_id",
+                        "Match ID, e.g. NA1_1234567890",
+                    )
+                    .required(true),
+                ),
+        );
+
+        let heatmap = player_options(
+            CreateCommand::new("heatmap")
+                .description("Where a player gets kills and dies on Summoner's Rift"),
+        )
+        .add_option(
+            CreateCommandOption::new(
+                serenity::all::CommandOptionType::Integer,
+                "game_count",
+                "Number of games to include, each costs a timeline request",
+            )
+            .min_int_value(1)
+            .max_int_value(50)
+            .required(false),
+        );
+
+        let duos = player_options(
+            CreateCommand::new("duos").description("Find who a player queues with most often"),
+        )
+        .add_option(
+            CreateCommandOption::new(
+                serenity::all::CommandOptionType::Integer,
+                "game_count",
+                "Number of games to scan",
+            )
+            .min_int_value(1)
+            .max_int_value(100)
+            .required(false),
+        );
+
+        let compare = [
+            ("player_a", "First player's Riot ID name"),
+            ("tag_a", "First player's tag"),
+            ("player_b", "Second player's Riot ID name"),
+            ("tag_b", "Second player's tag"),
+        ]
+        .into_iter()
+        .fold(
+            CreateCommand::new("compare").description("Compare two players side by side"),
+            |command, (name, description)| {
+                command.add_option(
+                    CreateCommandOption::new(
+                        serenity::all::CommandOptionType::String,
+                        name,
+                        description,
+                    )
+                    .required(true),
+                )
+            },
+        )
+        .add_option(region_option())
+        .add_option(
+            CreateCommandOption::new(
+                serenity::all::CommandOptionType::Integer,
+                "game_count",
+                "Number of games per player",
+            )
+            .min_int_value(1)
+            .max_int_value(100)
+            .required(false),
+        );
+
+        let season = player_options(
+            CreateCommand::new("season")
+                .description("Games, monthly winrate, champion pool and roles this season"),
+        );
+
+        let playtime = player_options(
+            CreateCommand::new("playtime")
+                .description("Hours played, sessions and time of day winrate from stored games"),
+        )
+        .add_option(
+            CreateCommandOption::new(
+                serenity::all::CommandOptionType::Integer,
+                "days",
+                "Number of days to count",
+            )
+            .min_int_value(1)
+            .max_int_value(365)
+            .required(false),
+        );
+
+        let commands = vec![
+            matches,
+            build,
+            counter,
+            tierlist,
+            live,
+            stats,
+            link,
+            accounts,
+            shortcut,
+            run,
+            settings,
+            lphistory,
+            leaderboard,
+            export,
+            mastery,
+            scout,
+            timeline,
+            heatmap,
+            duos,
+            compare,
+            season,
+            playtime,
+        ];
         let commands = &self
             .discord_guild_id
             .set_commands(&ctx.http, commands)
@@ -151,6 +751,43 @@ impl EventHandler for Bot {
             .unwrap();
 
         info!("Registered commands: {:#?}", commands);
+
+        if !self.tasks_started.swap(true, Ordering::SeqCst) {
+            tokio::spawn(announcements::poll_finished_games(
+                ctx.http.clone(),
+                self.client.clone(),
+                self.riot_api_key.clone(),
+                self.store.clone(),
+                self.riot_limiter.clone(),
+            ));
+            tokio::spawn(rank_alerts::poll_rank_changes(
+                ctx.http.clone(),
+                self.client.clone(),
+                self.riot_api_key.clone(),
+                self.store.clone(),
+                self.riot_limiter.clone(),
+            ));
+            tokio::spawn(live_notifications::poll_live_games(
+                ctx.http.clone(),
+                self.client.clone(),
+                self.riot_api_key.clone(),
+                self.store.clone(),
+                self.riot_limiter.clone(),
+            ));
+            tokio::spawn(recap::post_weekly_recaps(
+                ctx.http.clone(),
+                self.client.clone(),
+                self.riot_api_key.clone(),
+                self.store.clone(),
+                self.riot_limiter.clone(),
+            ));
+            tokio::spawn(season::backfill_seasons(
+                self.client.clone(),
+                self.riot_api_key.clone(),
+                self.store.clone(),
+                self.riot_limiter.clone(),
+            ));
+        }
     }
 
     async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
@@ -173,21 +810,6 @@ impl EventHandler for Bot {
                     "matches" => {
                         let iter = command.data.options.iter();
 
-                        let player_name = iter
-                            .clone()
-                            .find(|opt| opt.name == "player_name")
-                            .and_then(|opt| opt.value.as_str())
-                            .unwrap();
-                        let tag = iter
-                            .clone()
-                            .find(|opt| opt.name == "tag")
-                            .and_then(|opt| opt.value.as_str())
-                            .unwrap();
-                        let region = iter
-                            .clone()
-                            .find(|opt| opt.name == "region")
-                            .and_then(|opt| opt.value.as_str())
-                            .unwrap_or("americas");
                         let game_count = iter
                             .clone()
                             .find(|opt| opt.name == "game_count")
@@ -201,15 +823,22 @@ impl EventHandler for Bot {
                                 20
                             });
 
-                        let matches_command_result = matches::handle_matches_command(
-                            player_name,
-                            tag,
-                            region,
-                            game_count,
-                            &self.riot_api_key,
-                            &self.client,
-                        )
-                        .await;
+                        let matches_command_result = match self.resolve_account(&command).await {
+                            Ok((player_name, tag, platform)) => {
+                                matches::handle_matches_command(
+                                    &player_name,
+                                    &tag,
+                                    platform,
+                                    game_count,
+                                    &self.riot_api_key,
+                                    &self.client,
+                                    &self.store,
+                                    &self.riot_limiter,
+                                )
+                                .await
+                            }
+                            Err(err) => Err(err.into()),
+                        };
                         match matches_command_result {
                             Ok(matches_command_result) => {
                                 Ok::<
@@ -233,34 +862,6 @@ impl EventHandler for Bot {
                             }
                         }
                     }
-                    "john" => {
-                        let matches_command_result = matches::handle_matches_command(
-                            "SolarKnight0",
-                            "NA2",
-                            "Americas",
-                            20,
-                            &self.riot_api_key,
-                            &self.client,
-                        )
-                        .await;
-                        match matches_command_result {
-                            Ok(matches_command_result) => Ok((matches_command_result, None)),
-                            Err(err) => {
-                                println!("Error: {}", err);
-                                Ok((
-                                    DiscordOutput::new(
-                                        Colour::RED,
-                                        "".to_string(),
-                                        vec![],
-                                        err.to_string(),
-                                        "".to_string(),
-                                        "".to_string(),
-                                    ),
-                                    None,
-                                ))
-                            }
-                        }
-                    }
                     "counter" => {
                         let iter = command.data.options.iter();
                         let champion = iter
@@ -375,70 +976,1022 @@ impl EventHandler for Bot {
                             }
                         }
                     }
-                    command => unreachable!("Unknown command: {}", command),
-                }
-                .expect("");
-
-            // Duration of finding build info with scraping takes too long compared to champ select screen, this first handles runes then later outputs build
-            if match command.data.name.as_str() {
-                "build" => true,
-                _ => false,
-            } {
-                let duration = start.elapsed();
-                let discord_output = response_content.0;
-                let document = response_content.1.expect("");
+                    "live" => {
+                        let live_result = match self.resolve_account(&command).await {
+                            Ok((player_name, tag, platform)) => {
+                                live::handle_live_command(
+                                    &player_name,
+                                    &tag,
+                                    platform,
+                                    &self.riot_api_key,
+                                    &self.client,
+                                    &self.riot_limiter,
+                                )
+                                .await
+                            }
+                            Err(err) => Err(err.into()),
+                        };
 
-                let DiscordOutput {
-                    color,
-                    description,
-                    fields,
-                    footer: _,
-                    title,
-                    content,
-                } = &discord_output;
-
-                let data = CreateEmbed::new()
-                    .title(title)
-                    .description(description)
-                    .color(*color)
-                    .fields(fields.clone())
-                    .footer(CreateEmbedFooter::new(format!(
-                        "Initial rune time {:?}",
-                        duration
-                    )));
-
-                let edit_builder = EditInteractionResponse::new().content(content).embed(data);
-                command
-                    .edit_response(&ctx.http, edit_builder)
-                    .await
-                    .unwrap();
+                        match live_result {
+                            Ok(result) => Ok((result, None)),
+                            Err(err) => {
+                                println!("Error: {}", err);
+                                Ok((
+                                    DiscordOutput::new(
+                                        Colour::RED,
+                                        "".to_string(),
+                                        vec![],
+                                        err.to_string(),
+                                        "".to_string(),
+                                        "".to_string(),
+                                    ),
+                                    None,
+                                ))
+                            }
+                        }
+                    }
+                    "stats" => {
+                        let iter = command.data.options.iter();
 
-                response_content = (
-                    build::handle_build_continuation(&tab, document, discord_output),
-                    None,
-                );
-            }
+                        let game_count = iter
+                            .clone()
+                            .find(|opt| opt.name == "game_count")
+                            .and_then(|opt| opt.value.as_i64())
+                            .unwrap_or(20);
 
-            let duration = start.elapsed();
+                        let stats_result = if account_option(&command) == Some(ALL_ACCOUNTS) {
+                            match self.resolve_all_accounts(&command).await {
+                                Ok(accounts) => {
+                                    stats::handle_all_accounts_stats_command(
+                                        &accounts,
+                                        game_count,
+                                        &self.riot_api_key,
+                                        &self.client,
+                                        &self.store,
+                                        &self.riot_limiter,
+                                    )
+                                    .await
+                                }
+                                Err(err) => Err(err.into()),
+                            }
+                        } else {
+                            match self.resolve_account(&command).await {
+                                Ok((player_name, tag, platform)) => {
+                                    stats::handle_stats_command(
+                                        &player_name,
+                                        &tag,
+                                        platform,
+                                        game_count,
+                                        &self.riot_api_key,
+                                        &self.client,
+                                        &self.store,
+                                        &self.riot_limiter,
+                                    )
+                                    .await
+                                }
+                                Err(err) => Err(err.into()),
+                            }
+                        };
 
-            let DiscordOutput {
-                color,
-                description,
-                fields,
-                footer: _,
-                title,
-                content,
-            } = response_content.0;
-
-            let data = CreateEmbed::new()
-                .title(title)
-                .description(description)
-                .color(color)
-                .fields(fields)
-                .footer(CreateEmbedFooter::new(format!("({:?})", duration)));
-
-            let edit_builder = EditInteractionResponse::new().content(content).embed(data);
-            command
+                        match stats_result {
+                            Ok(result) => Ok((result, None)),
+                            Err(err) => {
+                                println!("Error: {}", err);
+                                Ok((
+                                    DiscordOutput::new(
+                                        Colour::RED,
+                                        "".to_string(),
+                                        vec![],
+                                        err.to_string(),
+                                        "".to_string(),
+                                        "".to_string(),
+                                    ),
+                                    None,
+                                ))
+                            }
+                        }
+                    }
+                    "link" => {
+                        let iter = command.data.options.iter();
+
+                        let riot_id = iter
+                            .clone()
+                            .find(|opt| opt.name == "riot_id")
+                            .and_then(|opt| opt.value.as_str())
+                            .unwrap();
+                        let platform = iter
+                            .clone()
+                            .find(|opt| opt.name == "region")
+                            .and_then(|opt| opt.value.as_str())
+                            .and_then(|region| Platform::from_str(region).ok())
+                            .unwrap_or_default();
+                        let primary = iter
+                            .clone()
+                            .find(|opt| opt.name == "primary")
+                            .and_then(|opt| opt.value.as_bool())
+                            .unwrap_or(false);
+
+                        let link_result = link::handle_link_command(
+                            command.user.id,
+                            command.guild_id,
+                            riot_id,
+                            platform,
+                            primary,
+                            &self.riot_api_key,
+                            &self.client,
+                            &self.store,
+                            &self.riot_limiter,
+                        )
+                        .await;
+
+                        match link_result {
+                            Ok(result) => Ok((result, None)),
+                            Err(err) => {
+                                println!("Error: {}", err);
+                                Ok((
+                                    DiscordOutput::new(
+                                        Colour::RED,
+                                        "".to_string(),
+                                        vec![],
+                                        err.to_string(),
+                                        "".to_string(),
+                                        "".to_string(),
+                                    ),
+                                    None,
+                                ))
+                            }
+                        }
+                    }
+                    "accounts" => {
+                        let subcommand = command.data.options.first().unwrap();
+
+                        let accounts_result =
+                            link::handle_accounts_command(command.user.id, subcommand, &self.store)
+                                .await;
+
+                        match accounts_result {
+                            Ok(result) => Ok((result, None)),
+                            Err(err) => {
+                                println!("Error: {}", err);
+                                Ok((
+                                    DiscordOutput::new(
+                                        Colour::RED,
+                                        "".to_string(),
+                                        vec![],
+                                        err.to_string(),
+                                        "".to_string(),
+                                        "".to_string(),
+                                    ),
+                                    None,
+                                ))
+                            }
+                        }
+                    }
+                    "shortcut" => {
+                        let guild_id = command.guild_id.unwrap_or(self.discord_guild_id);
+                        let subcommand = command.data.options.first().unwrap();
+
+                        let shortcut_result =
+                            shortcuts::handle_shortcut_command(guild_id, subcommand, &self.store)
+                                .await;
+
+                        match shortcut_result {
+                            Ok(result) => Ok((result, None)),
+                            Err(err) => {
+                                println!("Error: {}", err);
+                                Ok((
+                                    DiscordOutput::new(
+                                        Colour::RED,
+                                        "".to_string(),
+                                        vec![],
+                                        err.to_string(),
+                                        "".to_string(),
+                                        "".to_string(),
+                                    ),
+                                    None,
+                                ))
+                            }
+                        }
+                    }
+                    "run" => {
+                        let guild_id = command.guild_id.unwrap_or(self.discord_guild_id);
+                        let name = command
+                            .data
+                            .options
+                            .iter()
+                            .find(|opt| opt.name == "name")
+                            .and_then(|opt| opt.value.as_str())
+                            .unwrap();
+
+                        let run_result = shortcuts::handle_run_command(
+                            guild_id,
+                            name,
+                            &self.riot_api_key,
+                            &self.client,
+                            &self.store,
+                            &self.riot_limiter,
+                        )
+                        .await;
+
+                        match run_result {
+                            Ok(result) => Ok((result, None)),
+                            Err(err) => {
+                                println!("Error: {}", err);
+                                Ok((
+                                    DiscordOutput::new(
+                                        Colour::RED,
+                                        "".to_string(),
+                                        vec![],
+                                        err.to_string(),
+                                        "".to_string(),
+                                        "".to_string(),
+                                    ),
+                                    None,
+                                ))
+                            }
+                        }
+                    }
+                    "settings" => {
+                        let guild_id = command.guild_id.unwrap_or(self.discord_guild_id);
+                        let subcommand = command.data.options.first().unwrap();
+
+                        let settings_result =
+                            settings::handle_settings_command(guild_id, subcommand, &self.store)
+                                .await;
+
+                        match settings_result {
+                            Ok(result) => Ok((result, None)),
+                            Err(err) => {
+                                println!("Error: {}", err);
+                                Ok((
+                                    DiscordOutput::new(
+                                        Colour::RED,
+                                        "".to_string(),
+                                        vec![],
+                                        err.to_string(),
+                                        "".to_string(),
+                                        "".to_string(),
+                                    ),
+                                    None,
+                                ))
+                            }
+                        }
+                    }
+                    "lphistory" => {
+                        let days = command
+                            .data
+                            .options
+                            .iter()
+                            .find(|opt| opt.name == "days")
+                            .and_then(|opt| opt.value.as_i64())
+                            .unwrap_or(30);
+
+                        let lphistory_result = match self.resolve_account(&command).await {
+                            Ok((player_name, tag, platform)) => {
+                                lphistory::handle_lphistory_command(
+                                    &player_name,
+                                    &tag,
+                                    platform,
+                                    days,
+                                    &self.riot_api_key,
+                                    &self.client,
+                                    &self.store,
+                                    &self.riot_limiter,
+                                )
+                                .await
+                            }
+                            Err(err) => Err(err.into()),
+                        };
+
+                        match lphistory_result {
+                            Ok(result) => Ok((result, None)),
+                            Err(err) => {
+                                println!("Error: {}", err);
+                                Ok((
+                                    DiscordOutput::new(
+                                        Colour::RED,
+                                        "".to_string(),
+                                        vec![],
+                                        err.to_string(),
+                                        "".to_string(),
+                                        "".to_string(),
+                                    ),
+                                    None,
+                                ))
+                            }
+                        }
+                    }
+                    "leaderboard" => {
+                        let options = command.data.options.iter();
+                        let metric = options
+                            .clone()
+                            .find(|opt| opt.name == "metric")
+                            .and_then(|opt| opt.value.as_str())
+                            .unwrap_or("rank");
+                        let days = options
+                            .clone()
+                            .find(|opt| opt.name == "days")
+                            .and_then(|opt| opt.value.as_i64())
+                            .unwrap_or(7);
+                        let page = options
+                            .clone()
+                            .find(|opt| opt.name == "page")
+                            .and_then(|opt| opt.value.as_i64())
+                            .unwrap_or(1);
+
+                        let leaderboard_result = leaderboard::handle_leaderboard_command(
+                            command.guild_id.unwrap_or(self.discord_guild_id),
+                            metric,
+                            days,
+                            page as usize,
+                            &self.store,
+                        )
+                        .await;
+
+                        match leaderboard_result {
+                            Ok(result) => Ok((result, None)),
+                            Err(err) => {
+                                println!("Error: {}", err);
+                                Ok((
+                                    DiscordOutput::new(
+                                        Colour::RED,
+                                        "".to_string(),
+                                        vec![],
+                                        err.to_string(),
+                                        "".to_string(),
+                                        "".to_string(),
+                                    ),
+                                    None,
+                                ))
+                            }
+                        }
+                    }
+                    "export" => {
+                        let iter = command.data.options.iter();
+
+                        let game_count = iter
+                            .clone()
+                            .find(|opt| opt.name == "game_count")
+                            .and_then(|opt| opt.value.as_i64())
+                            .unwrap_or(20);
+                        let format = iter
+                            .clone()
+                            .find(|opt| opt.name == "format")
+                            .and_then(|opt| opt.value.as_str())
+                            .unwrap_or("csv");
+
+                        let export_result = match self.resolve_account(&command).await {
+                            Ok((player_name, tag, platform)) => {
+                                export::handle_export_command(
+                                    &player_name,
+                                    &tag,
+                                    platform,
+                                    game_count,
+                                    format,
+                                    &self.riot_api_key,
+                                    &self.client,
+                                    &self.store,
+                                    &self.riot_limiter,
+                                )
+                                .await
+                            }
+                            Err(err) => Err(err.into()),
+                        };
+
+                        match export_result {
+                            Ok(result) => Ok((result, None)),
+                            Err(err) => {
+                                println!("Error: {}", err);
+                                Ok((
+                                    DiscordOutput::new(
+                                        Colour::RED,
+                                        "".to_string(),
+                                        vec![],
+                                        err.to_string(),
+                                        "".to_string(),
+                                        "".to_string(),
+                                    ),
+                                    None,
+                                ))
+                            }
+                        }
+                    }
+                    "mastery" => {
+                        let champion = command
+                            .data
+                            .options
+                            .iter()
+                            .find(|opt| opt.name == "champion")
+                            .and_then(|opt| opt.value.as_str());
+
+                        let mastery_result = match self.resolve_account(&command).await {
+                            Ok((player_name, tag, platform)) => {
+                                mastery::handle_mastery_command(
+                                    &player_name,
+                                    &tag,
+                                    platform,
+                                    champion,
+                                    &self.riot_api_key,
+                                    &self.client,
+                                    &self.store,
+                                    &self.riot_limiter,
+                                )
+                                .await
+                            }
+                            Err(err) => Err(err.into()),
+                        };
+
+                        match mastery_result {
+                            Ok(result) => Ok((result, None)),
+                            Err(err) => {
+                                println!("Error: {}", err);
+                                Ok((
+                                    DiscordOutput::new(
+                                        Colour::RED,
+                                        "".to_string(),
+                                        vec![],
+                                        err.to_string(),
+                                        "".to_string(),
+                                        "".to_string(),
+                                    ),
+                                    None,
+                                ))
+                            }
+                        }
+                    }
+                    "scout" => {
+                        let iter = command.data.options.iter();
+
+                        let text = iter
+                            .clone()
+                            .find(|opt| opt.name == "text")
+                            .and_then(|opt| opt.value.as_str())
+                            .unwrap();
+                        let platform = iter
+                            .clone()
+                            .find(|opt| opt.name == "region")
+                            .and_then(|opt| opt.value.as_str())
+                            .and_then(|region| Platform::from_str(region).ok())
+                            .unwrap_or_default();
+
+                        let scout_result = scout::handle_scout_command(
+                            text,
+                            platform,
+                            &self.riot_api_key,
+                            &self.client,
+                            &self.store,
+                            &self.riot_limiter,
+                        )
+                        .await;
+
+                        match scout_result {
+                            Ok(result) => Ok((result, None)),
+                            Err(err) => {
+                                println!("Error: {}", err);
+                                Ok((
+                                    DiscordOutput::new(
+                                        Colour::RED,
+                                        "".to_string(),
+                                        vec![],
+                                        err.to_string(),
+                                        "".to_string(),
+                                        "".to_string(),
+                                    ),
+                                    None,
+                                ))
+                            }
+                        }
+                    }
+                    "timeline" => {
+                        let match_id = command
+                            .data
+                            .options
+                            .iter()
+                            .find(|opt| opt.name == "match_id")
+                            .and_then(|opt| opt.value.as_str())
+                            .unwrap()
+                            .trim()
+                            .to_uppercase();
+
+                        let timeline_result = match self.resolve_account(&command).await {
+                            Ok((player_name, tag, platform)) => {
+                                timeline::handle_timeline_command(
+                                    &match_id,
+                                    &player_name,
+                                    &tag,
+                                    platform,
+                                    &self.riot_api_key,
+                                    &self.client,
+                                    &self.store,
+                                    &self.riot_limiter,
+                                )
+                                .await
+                            }
+                            Err(err) => Err(err.into()),
+                        };
+
+                        match timeline_result {
+                            Ok(result) => Ok((result, None)),
+                            Err(err) => {
+                                println!("Error: {}", err);
+                                Ok((
+                                    DiscordOutput::new(
+                                        Colour::RED,
+                                        "".to_string(),
+                                        vec![],
+                                        err.to_string(),
+                                        "".to_string(),
+                                        "".to_string(),
+                                    ),
+                                    None,
+                                ))
+                            }
+                        }
+                    }
+                    "heatmap" => {
+                        let game_count = command
+                            .data
+                            .options
+                            .iter()
+                            .find(|opt| opt.name == "game_count")
+                            .and_then(|opt| opt.value.as_i64())
+                            .unwrap_or(20);
+
+                        let heatmap_result = match self.resolve_account(&command).await {
+                            Ok((player_name, tag, platform)) => heatmap::handle_heatmap_command(
+                                &player_name,
+                                &tag,
+                                platform,
+                                game_count,
+                                &self.riot_api_key,
+                                &self.client,
+                                &self.store,
+                                &self.riot_limiter,
+                            )
+                            .await
+                            .map_err(|err| err.to_string()),
+                            Err(err) => Err(err),
+                        };
+
+                        match heatmap_result {
+                            Ok(result) => Ok((result, None)),
+                            Err(err) => {
+                                println!("Error: {}", err);
+                                Ok((
+                                    DiscordOutput::new(
+                                        Colour::RED,
+                                        "".to_string(),
+                                        vec![],
+                                        err,
+                                        "".to_string(),
+                                        "".to_string(),
+                                    ),
+                                    None,
+                                ))
+                            }
+                        }
+                    }
+                    "duos" => {
+                        let game_count = command
+                            .data
+                            .options
+                            .iter()
+                            .find(|opt| opt.name == "game_count")
+                            .and_then(|opt| opt.value.as_i64())
+                            .unwrap_or(50);
+
+                        let duos_result = match self.resolve_account(&command).await {
+                            Ok((player_name, tag, platform)) => duos::handle_duos_command(
+                                &player_name,
+                                &tag,
+                                platform,
+                                game_count,
+                                command.guild_id.unwrap_or(self.discord_guild_id),
+                                &self.riot_api_key,
+                                &self.client,
+                                &self.store,
+                                &self.riot_limiter,
+                            )
+                            .await
+                            .map_err(|err| err.to_string()),
+                            Err(err) => Err(err),
+                        };
+
+                        match duos_result {
+                            Ok(result) => Ok((result, None)),
+                            Err(err) => {
+                                println!("Error: {}", err);
+                                Ok((
+                                    DiscordOutput::new(
+                                        Colour::RED,
+                                        "".to_string(),
+                                        vec![],
+                                        err,
+                                        "".to_string(),
+                                        "".to_string(),
+                                    ),
+                                    None,
+                                ))
+                            }
+                        }
+                    }
+                    "compare" => {
+                        let iter = command.data.options.iter();
+                        let option = |name: &str| {
+                            iter.clone()
+                                .find(|opt| opt.name == name)
+                                .and_then(|opt| opt.value.as_str())
+                                .unwrap_or_default()
+                                .trim()
+                        };
+                        let platform = Platform::from_str(option("region")).unwrap_or_default();
+                        let game_count = iter
+                            .clone()
+                            .find(|opt| opt.name == "game_count")
+                            .and_then(|opt| opt.value.as_i64())
+                            .unwrap_or(20);
+
+                        let compare_result = compare::handle_compare_command(
+                            option("player_a"),
+                            option("tag_a"),
+                            option("player_b"),
+                            option("tag_b"),
+                            platform,
+                            game_count,
+                            &self.riot_api_key,
+                            &self.client,
+                            &self.store,
+                            &self.riot_limiter,
+                        )
+                        .await
+                        .map_err(|err| err.to_string());
+
+                        match compare_result {
+                            Ok(result) => Ok((result, None)),
+                            Err(err) => {
+                                println!("Error: {}", err);
+                                Ok((
+                                    DiscordOutput::new(
+                                        Colour::RED,
+                                        "".to_string(),
+                                        vec![],
+                                        err,
+                                        "".to_string(),
+                                        "".to_string(),
+                                    ),
+                                    None,
+                                ))
+                            }
+                        }
+                    }
+                    "season" => {
+                        let season_result = match self.resolve_account(&command).await {
+                            Ok((player_name, tag, platform)) => season::handle_season_command(
+                                &player_name,
+                                &tag,
+                                platform,
+                                &self.riot_api_key,
+                                &self.client,
+                                &self.store,
+                                &self.riot_limiter,
+                            )
+                            .await
+                            .map_err(|err| err.to_string()),
+                            Err(err) => Err(err),
+                        };
+
+                        match season_result {
+                            Ok(result) => Ok((result, None)),
+                            Err(err) => {
+                                println!("Error: {}", err);
+                                Ok((
+                                    DiscordOutput::new(
+                                        Colour::RED,
+                                        "".to_string(),
+                                        vec![],
+                                        err,
+                                        "".to_string(),
+                                        "".to_string(),
+                                    ),
+                                    None,
+                                ))
+                            }
+                        }
+                    }
+                    "playtime" => {
+                        let days = command
+                            .data
+                            .options
+                            .iter()
+                            .find(|opt| opt.name == "days")
+                            .and_then(|opt| opt.value.as_i64())
+                            .unwrap_or(14);
+
+                        let playtime_result = match self.resolve_account(&command).await {
+                            Ok((player_name, tag, platform)) => playtime::handle_playtime_command(
+                                &player_name,
+                                &tag,
+                                platform,
+                                days,
+                                &self.riot_api_key,
+                                &self.client,
+                                &self.store,
+                                &self.riot_limiter,
+                            )
+                            .await
+                            .map_err(|err| err.to_string()),
+                            Err(err) => Err(err),
+                        };
+
+                        match playtime_result {
+                            Ok(result) => Ok((result, None)),
+                            Err(err) => {
+                                println!("Error: {}", err);
+                                Ok((
+                                    DiscordOutput::new(
+                                        Colour::RED,
+                                        "".to_string(),
+                                        vec![],
+                                        err,
+                                        "".to_string(),
+                                        "".to_string(),
+                                    ),
+                                    None,
+                                ))
+                            }
+                        }
+                    }
+                    command => unreachable!("Unknown command: {}", command),
+                }
+                .expect("");
+
+            // Duration of finding build info with scraping takes too long compared to champ select screen, this first handles runes then later outputs build
+            if match command.data.name.as_str() {
+                "build" => true,
+                _ => false,
+            } {
+                let duration = start.elapsed();
+                let discord_output = response_content.0;
+                let document = response_content.1.expect("");
+
+                let edit_builder = output_to_response(
+                    &discord_output,
+                    format!("Initial rune time {:?}", duration),
+                );
+                command
+                    .edit_response(&ctx.http, edit_builder)
+                    .await
+                    .unwrap();
+
+                response_content = (
+                    build::handle_build_continuation(&tab, document, discord_output),
+                    None,
+                );
+            }
+
+            let duration = start.elapsed();
+
+            let edit_builder = output_to_response(&response_content.0, format!("({:?})", duration));
+            command
+                .edit_response(&ctx.http, edit_builder)
+                .await
+                .unwrap();
+        } else if let Interaction::Autocomplete(autocomplete) = interaction {
+            let Some(focused) = autocomplete.data.autocomplete() else {
+                return;
+            };
+            let names = match focused.name {
+                "name" => {
+                    let guild_id = autocomplete.guild_id.unwrap_or(self.discord_guild_id);
+                    shortcuts::autocomplete_shortcut_names(guild_id, focused.value, &self.store)
+                        .await
+                }
+                // Player commands pick one of the looked up user's accounts, /accounts the
+                // caller's own
+                "account" | "riot_id" => {
+                    let user_id = autocomplete
+                        .data
+                        .options
+                        .iter()
+                        .find(|opt| opt.name == "user")
+                        .and_then(|opt| opt.value.as_user_id())
+                        .unwrap_or(autocomplete.user.id);
+                    link::autocomplete_linked_accounts(
+                        user_id,
+                        focused.value,
+                        focused.name == "account" && autocomplete.data.name == "stats",
+                        &self.store,
+                    )
+                    .await
+                }
+                _ => return,
+            };
+            let response = names
+                .into_iter()
+                .fold(CreateAutocompleteResponse::new(), |response, name| {
+                    response.add_string_choice(name.clone(), name)
+                });
+
+            autocomplete
+                .create_response(&ctx.http, CreateInteractionResponse::Autocomplete(response))
+                .await
+                .unwrap();
+        } else if let Interaction::Component(component) = interaction {
+            // Leaderboard pages come from the store, so the message is updated in place
+            if let Some((metric, days, page)) =
+                leaderboard::parse_leaderboard_button(&component.data.custom_id)
+            {
+                let guild_id = component.guild_id.unwrap_or(self.discord_guild_id);
+                let discord_output = leaderboard::handle_leaderboard_command(
+                    guild_id,
+                    metric,
+                    days,
+                    page,
+                    &self.store,
+                )
+                .await
+                .unwrap_or_else(|err| {
+                    DiscordOutput::new(
+                        Colour::RED,
+                        "".to_string(),
+                        vec![],
+                        err.to_string(),
+                        "".to_string(),
+                        "".to_string(),
+                    )
+                });
+                let builder = CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::new()
+                        .embed(discord_output.to_embed())
+                        .components(discord_output.to_components()),
+                );
+                component.create_response(&ctx.http, builder).await.unwrap();
+                return;
+            }
+
+            if let Some(puuid) = component.data.custom_id.strip_prefix("live:") {
+                let builder =
+                    CreateInteractionResponse::Defer(CreateInteractionResponseMessage::new());
+                component.create_response(&ctx.http, builder).await.unwrap();
+                let start = Instant::now();
+
+                let account = self
+                    .store
+                    .read()
+                    .await
+                    .linked_accounts
+                    .values()
+                    .flatten()
+                    .find(|account| account.puuid == puuid)
+                    .cloned();
+                let live_result = match account {
+                    Some(account) => live::handle_live_command(
+                        &account.player_name,
+                        &account.tag,
+                        account.platform,
+                        &self.riot_api_key,
+                        &self.client,
+                        &self.riot_limiter,
+                    )
+                    .await
+                    .map_err(|err| err.to_string()),
+                    None => Err("That player is no longer linked".to_string()),
+                };
+
+                let discord_output = live_result.unwrap_or_else(|err| {
+                    println!("Error: {}", err);
+                    DiscordOutput::new(
+                        Colour::RED,
+                        "".to_string(),
+                        vec![],
+                        err,
+                        "".to_string(),
+                        "".to_string(),
+                    )
+                });
+                let edit_builder =
+                    output_to_response(&discord_output, format!("({:?})", start.elapsed()));
+                component
+                    .edit_response(&ctx.http, edit_builder)
+                    .await
+                    .unwrap();
+                return;
+            }
+
+            if let Some((match_id, participant_id)) = component
+                .data
+                .custom_id
+                .strip_prefix("coach:")
+                .and_then(|ids| ids.rsplit_once(':'))
+                .and_then(|(match_id, participant_id)| {
+                    Some((match_id, participant_id.parse::<i32>().ok()?))
+                })
+            {
+                let builder =
+                    CreateInteractionResponse::Defer(CreateInteractionResponseMessage::new());
+                component.create_response(&ctx.http, builder).await.unwrap();
+                let browser = Browser::new(
+                    LaunchOptionsBuilder::default()
+                        .headless(true)
+                        .build()
+                        .unwrap(),
+                )
+                .unwrap();
+
+                let tab = browser.new_tab().unwrap();
+                let start = Instant::now();
+
+                let coach_result = coach::handle_coach_command(
+                    match_id,
+                    participant_id,
+                    &self.riot_api_key,
+                    &self.client,
+                    &self.store,
+                    &self.riot_limiter,
+                    &tab,
+                )
+                .await
+                .map_err(|err| err.to_string());
+
+                let discord_output = coach_result.unwrap_or_else(|err| {
+                    println!("Error: {}", err);
+                    DiscordOutput::new(
+                        Colour::RED,
+                        "".to_string(),
+                        vec![],
+                        err,
+                        "".to_string(),
+                        "".to_string(),
+                    )
+                });
+                let edit_builder =
+                    output_to_response(&discord_output, format!("({:?})", start.elapsed()));
+                component
+                    .edit_response(&ctx.http, edit_builder)
+                    .await
+                    .unwrap();
+                return;
+            }
+
+            // Buttons encode the command they replay in their custom id, e.g. `build:ahri:zed`
+            let Some((you, enemy)) = component
+                .data
+                .custom_id
+                .strip_prefix("build:")
+                .and_then(|champions| champions.split_once(':'))
+            else {
+                return;
+            };
+
+            let builder = CreateInteractionResponse::Defer(CreateInteractionResponseMessage::new());
+            component.create_response(&ctx.http, builder).await.unwrap();
+            let browser = Browser::new(
+                LaunchOptionsBuilder::default()
+                    .headless(true)
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap();
+
+            let tab = browser.new_tab().unwrap();
+            let start = Instant::now();
+
+            // Error is stringified up front since `Box<dyn Error>` can't be held across the awaits below
+            let build_result = build::handle_build_command(you, Some(enemy), None, &tab)
+                .await
+                .map_err(|err| err.to_string());
+
+            let mut discord_output = match build_result {
+                Ok((discord_output, Some(document))) => {
+                    let edit_builder = output_to_response(
+                        &discord_output,
+                        format!("Initial rune time {:?}", start.elapsed()),
+                    );
+                    component
+                        .edit_response(&ctx.http, edit_builder)
+                        .await
+                        .unwrap();
+
+                    build::handle_build_continuation(&tab, document, discord_output)
+                }
+                Ok((discord_output, None)) => discord_output,
+                Err(err) => {
+                    println!("Error: {}", err);
+                    DiscordOutput::new(
+                        Colour::RED,
+                        "".to_string(),
+                        vec![],
+                        err,
+                        "".to_string(),
+                        "".to_string(),
+                    )
+                }
+            };
+            discord_output.buttons = vec![];
+
+            let edit_builder =
+                output_to_response(&discord_output, format!("({:?})", start.elapsed()));
+            component
                 .edit_response(&ctx.http, edit_builder)
                 .await
                 .unwrap();
@@ -446,6 +1999,77 @@ impl EventHandler for Bot {
     }
 }
 
+fn output_to_response(discord_output: &DiscordOutput, footer: String) -> EditInteractionResponse {
+    let data = discord_output
+        .to_embed()
+        .footer(CreateEmbedFooter::new(footer));
+
+    discord_output.to_attachments().into_iter().fold(
+        EditInteractionResponse::new()
+            .content(&discord_output.content)
+            .embed(data)
+            .components(discord_output.to_components()),
+        |edit_builder, attachment| edit_builder.new_attachment(attachment),
+    )
+}
+
+/// Player lookup shared by every command about a Riot account. All optional, when no name
+/// is given the mentioned (or calling) user's linked account is used
+fn player_options(command: CreateCommand) -> CreateCommand {
+    command
+        .add_option(
+            CreateCommandOption::new(
+                serenity::all::CommandOptionType::String,
+                "player_name",
+                "Player Name",
+            )
+            .required(false),
+        )
+        .add_option(
+            CreateCommandOption::new(serenity::all::CommandOptionType::String, "tag", "playerTag")
+                .required(false),
+        )
+        .add_option(
+            CreateCommandOption::new(
+                serenity::all::CommandOptionType::User,
+                "user",
+                "Linked Discord user",
+            )
+            .required(false),
+        )
+        .add_option(region_option())
+        .add_option(
+            CreateCommandOption::new(
+                serenity::all::CommandOptionType::String,
+                "account",
+                "Which linked account, defaults to the primary one",
+            )
+            .set_autocomplete(true)
+            .required(false),
+        )
+}
+
+/// Linked account picked with the `account` option, if any
+fn account_option(command: &CommandInteraction) -> Option<&str> {
+    command
+        .data
+        .options
+        .iter()
+        .find(|opt| opt.name == "account")
+        .and_then(|opt| opt.value.as_str())
+        .map(str::trim)
+        .filter(|account| !account.is_empty())
+}
+
+/// Server picker shared by every command that looks up a Riot account
+fn region_option() -> CreateCommandOption {
+    Platform::iter().fold(
+        CreateCommandOption::new(serenity::all::CommandOptionType::String, "region", "Server")
+            .required(false),
+        |option, platform| option.add_string_choice(platform.display_name(), platform.as_ref()),
+    )
+}
+
 #[shuttle_runtime::main]
 async fn serenity(
     #[shuttle_runtime::Secrets] secret_store: SecretStore,
@@ -482,6 +2106,12 @@ pub async fn get_client(discord_token: &str, discord_guild_id: u64, riot_api_key
             client: reqwest::Client::new(),
             discord_guild_id: GuildId::new(discord_guild_id),
             riot_api_key: riot_api_key.to_owned(),
+            store: Arc::new(Store::load()),
+            tasks_started: AtomicBool::new(false),
+            riot_limiter: Arc::new(RateLimiter::new(vec![
+                (RIOT_REQUESTS_PER_SECOND, Duration::from_secs(1)),
+                (RIOT_REQUESTS_PER_TWO_MINUTES, Duration::from_secs(120)),
+            ])),
         })
         .await
         .expect("Err creating client")
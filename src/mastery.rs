@@ -0,0 +1,215 @@
+use chrono::Utc;
+use reqwest::Client;
+use serenity::all::Colour;
+
+use crate::{
+    matches::{fetch_matches, skipped_games_line},
+    shared::{
+        requests::{request_champion_mastery, request_for_puuid, request_top_masteries},
+        static_data::{champions, find_champion},
+        store::Store,
+        types::{AccountInfoContext, ChampionMasteryDto, DiscordOutput, Platform},
+        util::RateLimiter,
+    },
+    stats::{find_participant, PerformanceTotals},
+};
+
+const TOP_MASTERY_COUNT: i64 = 10;
+/// Games searched for the champion's recent record
+const RECENT_MATCH_COUNT: i64 = 50;
+
+#[allow(clippy::too_many_arguments)]
+pub async fn handle_mastery_command(
+    player_name: &str,
+    tag: &str,
+    platform: Platform,
+    champion: Option<&str>,
+    api_key: &str,
+    client: &Client,
+    store: &Store,
+    limiter: &RateLimiter,
+) -> Result<DiscordOutput, Box<dyn std::error::Error>> {
+    limiter.acquire().await;
+    let puuid = request_for_puuid(player_name, tag, platform, api_key, client).await?;
+    let champions = champions(client).await?;
+
+    let Some(champion) = champion else {
+        limiter.acquire().await;
+        let masteries =
+            request_top_masteries(&puuid, TOP_MASTERY_COUNT, platform, api_key, client).await?;
+        let lines = masteries
+            .iter()
+            .map(|mastery| {
+                let name = champions
+                    .get(&mastery.champion_id)
+                    .map(|champion| champion.name.as_str())
+                    .unwrap_or("Unknown");
+                format!(
+                    "{:<13}{:>3}{:>10} {:<9}{}",
+                    name.chars().take(12).collect::<String>(),
+                    mastery.champion_level,
+                    mastery.champion_points,
+                    format_played_ago(mastery.last_play_time),
+                    milestone_summary(mastery)
+                )
+            })
+            .collect::<Vec<String>>();
+
+        return Ok(DiscordOutput::new(
+            Colour::DARK_GREEN,
+            format!(
+                "```{:<13}{:>3}{:>10} {:<9}{}\n{}```",
+                "",
+                "Lvl",
+                "Points",
+                "Played",
+                "Milestone",
+                lines.join("\n")
+            ),
+            vec![],
+            "".to_string(),
+            format!("{}#{}'s Top Mastery", player_name, tag),
+            "".to_string(),
+        ));
+    };
+
+    let champion = find_champion(champions, champion)
+        .ok_or_else(|| format!("No champion named {}", champion))?;
+    let title = format!("{}#{}'s {} Mastery", player_name, tag, champion.name);
+    limiter.acquire().await;
+    let Some(mastery) =
+        request_champion_mastery(&puuid, champion.key.parse()?, platform, api_key, client).await?
+    else {
+        return Ok(DiscordOutput::new(
+            Colour::LIGHT_GREY,
+            format!("{}#{} has never played {}", player_name, tag, champion.name),
+            vec![],
+            "".to_string(),
+            title,
+            "".to_string(),
+        ));
+    };
+
+    let account_info_context = AccountInfoContext {
+        puuid: puuid.clone(),
+        player_name: player_name.to_string(),
+        tag: tag.to_string(),
+        platform,
+    };
+    let (matches, skipped) = fetch_matches(
+        RECENT_MATCH_COUNT,
+        api_key,
+        account_info_context,
+        client,
+        store,
+        limiter,
+    )
+    .await?;
+    // Match-v5 champion names don't always match Data Dragon ids (e.g. `FiddleSticks`), the
+    // numeric id does. Older cached matches only have the name
+    let champion_id: i64 = champion.key.parse()?;
+    let mut totals = PerformanceTotals::default();
+    for match_dto in matches.iter() {
+        if let Some(me) = find_participant(match_dto, &puuid).filter(|me| {
+            me.champion_id == champion_id
+                || (me.champion_id == 0 && me.champion_name.eq_ignore_ascii_case(&champion.id))
+        }) {
+            totals.add(me, &match_dto.info);
+        }
+    }
+
+    let mut fields = vec![
+        (
+            "Level".to_string(),
+            format!(
+                "{} ({} points to next)",
+                mastery.champion_level,
+                mastery.champion_points_until_next_level.max(0)
+            ),
+            true,
+        ),
+        (
+            "Points".to_string(),
+            mastery.champion_points.to_string(),
+            true,
+        ),
+        (
+            "Last Played".to_string(),
+            format_played_ago(mastery.last_play_time),
+            true,
+        ),
+        (
+            "Season Milestone".to_string(),
+            match next_milestone_requirements(&mastery) {
+                Some(required) => {
+                    format!("{}, next needs {}", milestone_summary(&mastery), required)
+                }
+                None => milestone_summary(&mastery),
+            },
+            false,
+        ),
+    ];
+    fields.push((
+        format!("Last {} games", RECENT_MATCH_COUNT),
+        if totals.games == 0 {
+            format!(
+                "No recent games on {}{}",
+                champion.name,
+                skipped_games_line(skipped)
+            )
+        } else {
+            format!(
+                "{} games - {:.0}% WR - {:.2} KDA - {:.1} CS/min{}",
+                totals.games,
+                totals.winrate(),
+                totals.kda(),
+                totals.cs_per_minute(),
+                skipped_games_line(skipped)
+            )
+        },
+        false,
+    ));
+
+    Ok(DiscordOutput::new(
+        Colour::DARK_GREEN,
+        "".to_string(),
+        fields,
+        "".to_string(),
+        title,
+        "".to_string(),
+    ))
+}
+
+/// e.g. `M2 S-,A`, milestones reached this season and grades towards the next one
+fn milestone_summary(mastery: &ChampionMasteryDto) -> String {
+    let mut summary = format!("M{}", mastery.champion_season_milestone);
+    if !mastery.milestone_grades.is_empty() {
+        summary.push_str(&format!(" {}", mastery.milestone_grades.join(",")));
+    }
+    summary
+}
+
+/// e.g. `1x A-, 3x B-`
+fn next_milestone_requirements(mastery: &ChampionMasteryDto) -> Option<String> {
+    let mut required: Vec<String> = mastery
+        .next_season_milestone
+        .as_ref()?
+        .require_grade_counts
+        .iter()
+        .map(|(grade, count)| format!("{}x {}", count, grade))
+        .collect();
+    required.sort();
+    (!required.is_empty()).then(|| required.join(", "))
+}
+
+/// How long ago `timestamp` (unix milliseconds) was, e.g. `3d ago`. Mastery can be years
+/// old, so a date without the year would be ambiguous
+fn format_played_ago(timestamp: i64) -> String {
+    let days = (Utc::now().timestamp_millis() - timestamp).max(0) / 86_400_000;
+    match days {
+        0 => "today".to_string(),
+        1..=29 => format!("{}d ago", days),
+        30..=364 => format!("{}mo ago", days / 30),
+        _ => format!("{}y ago", days / 365),
+    }
+}
@@ -1,30 +1,61 @@
-use futures::future::join_all;
+use std::collections::HashMap;
+
+use chrono::Utc;
+use futures::{stream, StreamExt};
 use reqwest::Client;
 use serenity::all::Color;
+use tracing::warn;
 
-use crate::shared::{
-    requests::{request_for_puuid, request_matches_from_puuid, send_request},
-    types::{AccountInfoContext, DiscordOutput, InfoDto, MatchDto},
+use crate::{
+    performance::performance_score,
+    shared::{
+        requests::{request_for_puuid, request_match, request_matches_from_puuid},
+        static_data::arena_augments,
+        store::Store,
+        types::{AccountInfoContext, DiscordOutput, InfoDto, MatchDto, Platform},
+        util::RateLimiter,
+    },
+    stats::current_streak,
 };
 
+/// Discord allows 5 rows of 5 buttons
+const MAX_COACH_BUTTONS: usize = 25;
+/// Uncached matches requested at once by `fetch_matches`
+const MAX_CONCURRENT_MATCH_REQUESTS: usize = 5;
+
+/// Embed field (name, value, inline) for one game
+pub type MatchField = (String, String, bool);
+
+#[allow(clippy::too_many_arguments)]
 pub async fn handle_matches_command(
     player_name: &str,
     tag: &str,
-    region: &str,
+    platform: Platform,
     game_count: i64,
     api_key: &str,
     client: &Client,
+    store: &Store,
+    limiter: &RateLimiter,
 ) -> Result<DiscordOutput, Box<dyn std::error::Error>> {
-    let puuid = request_for_puuid(player_name, tag, region, api_key, client).await?;
+    limiter.acquire().await;
+    let puuid = request_for_puuid(player_name, tag, platform, api_key, client).await?;
 
     let account_info_context = AccountInfoContext {
         puuid,
         player_name: player_name.to_string(),
         tag: tag.to_string(),
-        region: region.to_string(),
+        platform,
     };
 
-    return get_matches_info(game_count, api_key, account_info_context, client).await;
+    return get_matches_info(
+        game_count,
+        api_key,
+        account_info_context,
+        client,
+        store,
+        limiter,
+    )
+    .await;
 }
 
 async fn get_matches_info(
@@ -32,81 +63,181 @@ async fn get_matches_info(
     api_key: &str,
     account_info_context: AccountInfoContext,
     client: &Client,
+    store: &Store,
+    limiter: &RateLimiter,
 ) -> Result<DiscordOutput, Box<dyn std::error::Error>> {
     let AccountInfoContext {
-        region,
         puuid,
         player_name,
         tag,
+        ..
     } = account_info_context.clone();
-    let match_ids =
-        request_matches_from_puuid(game_count, api_key, account_info_context, client).await?;
-
-    let match_req_urls: Vec<String> = match_ids
-        .into_iter()
-        .map(|match_id| {
-            return format!(
-                "https://{}.api.riotgames.com/lol/match/v5/matches/{}",
-                region, match_id
-            );
-        })
-        .collect();
-
-    let match_futures = match_req_urls.clone().into_iter().map(|url| {
-        let client_clone = client.clone();
-        let api_key_clone = api_key.to_string();
-        async move { send_request(&url, Some(&api_key_clone), &client_clone).await }
-    });
+    let (match_data, skipped) = fetch_matches(
+        game_count,
+        api_key,
+        account_info_context,
+        client,
+        store,
+        limiter,
+    )
+    .await?;
 
-    let match_responses = join_all(match_futures).await;
+    // Arena games still render without augment names if Community Dragon is down
+    let no_augments = HashMap::new();
+    let augments = arena_augments(client).await.unwrap_or(&no_augments);
 
     let mut matches = Vec::new();
+    let mut coach_buttons = Vec::new();
     let mut count = 1;
-    for response in match_responses {
-        match response {
-            Ok(resp) => {
-                let match_data = resp.json::<MatchDto>().await?;
-                let match_info = get_match_info(match_data, count, puuid.clone()).unwrap();
-                matches.push(match_info);
-                count += 1;
+    for match_dto in match_data {
+        // Summoner's Rift games can be compared against u.gg, button ids stay under Discord's
+        // 100 characters by using the participant id instead of the PUUID
+        if let Some(me) = match_dto
+            .info
+            .participants
+            .iter()
+            .find(|p| p.puuid == puuid)
+        {
+            if match_dto.info.game_mode == "CLASSIC" && !me.is_remake() {
+                coach_buttons.push((
+                    format!(
+                        "coach:{}:{}",
+                        match_dto.metadata.match_id, me.participant_id
+                    ),
+                    format!("🎓 {}", count),
+                ));
             }
-            Err(_) => {}
         }
+        match get_match_info(match_dto, count, puuid.clone(), augments) {
+            Ok(match_info) => matches.push(match_info),
+            Err(err) => warn!("Skipping match {}: {}", count, err),
+        }
+        count += 1;
     }
 
-    let matches_len = matches.len();
-    let match_infos_iter = matches.into_iter();
-    let match_infos = match_infos_iter.clone().map(|(info, _)| info);
-    let win_count = match_infos_iter
-        .clone()
-        .fold(0, |acc, (_, win)| acc + win as i32);
+    let outcomes: Vec<bool> = matches.iter().filter_map(|(_, win)| *win).collect();
+    let matches_len = outcomes.len();
+    let win_count = outcomes.iter().filter(|win| **win).count();
+    let remake_count = matches.len() - matches_len;
+    let match_infos = matches.into_iter().map(|(info, _)| info);
 
     let discord_output = DiscordOutput::new(
         Color::DARK_GREEN,
         format!(
-            "Winrate: {}% ({}/{})",
-            (win_count as f32 / matches_len as f32) * 100.0,
+            "Winrate: {}% ({}/{}){}{}{}",
+            (win_count as f32 / matches_len.max(1) as f32) * 100.0,
             win_count,
             matches_len,
+            match remake_count {
+                0 => "".to_string(),
+                1 => ", 1 remake not counted".to_string(),
+                remakes => format!(", {} remakes not counted", remakes),
+            },
+            streak_line(&outcomes),
+            skipped_games_line(skipped)
         ),
         match_infos.collect(),
         "".to_string(),
         format!("{}#{}'s Matches", player_name, tag),
         "".to_string(),
-    );
+    )
+    .with_buttons(coach_buttons.into_iter().take(MAX_COACH_BUTTONS).collect());
 
     Ok(discord_output)
 }
 
-fn get_match_info(
+/// `outcomes` are newest game first, a single game is not worth calling a streak
+fn streak_line(outcomes: &[bool]) -> String {
+    match current_streak(outcomes) {
+        Some((true, wins)) if wins > 1 => format!("\n🔥 {} game win streak", wins),
+        Some((false, losses)) if losses > 1 => format!("\n💀 {} game loss streak", losses),
+        _ => "".to_string(),
+    }
+}
+
+/// Fetches the player's most recent `game_count` matches, along with how many failed to
+/// load and were skipped. Matches already in the store are read from disk, new ones are
+/// requested through `limiter` and added to the store
+pub async fn fetch_matches(
+    game_count: i64,
+    api_key: &str,
+    account_info_context: AccountInfoContext,
+    client: &Client,
+    store: &Store,
+    limiter: &RateLimiter,
+) -> Result<(Vec<MatchDto>, usize), Box<dyn std::error::Error>> {
+    let platform = account_info_context.platform;
+    let puuid = account_info_context.puuid.clone();
+    limiter.acquire().await;
+    let match_ids =
+        request_matches_from_puuid(game_count, api_key, account_info_context, client).await?;
+
+    let mut cached: Vec<Option<MatchDto>> = match_ids
+        .iter()
+        .map(|match_id| store.load_match(match_id))
+        .collect();
+
+    // A handful at a time, large game counts would otherwise fire every request at once
+    let missing: Vec<String> = match_ids
+        .iter()
+        .zip(&cached)
+        .filter(|(_, cached)| cached.is_none())
+        .map(|(match_id, _)| match_id.clone())
+        .collect();
+    let fetched: Vec<Option<MatchDto>> = stream::iter(missing)
+        .map(|match_id| {
+            let client = client.clone();
+            let api_key = api_key.to_string();
+            async move {
+                limiter.acquire().await;
+                request_match(&match_id, platform, &api_key, &client)
+                    .await
+                    .map_err(|err| warn!("Skipping match {}: {}", match_id, err))
+                    .ok()
+            }
+        })
+        .buffered(MAX_CONCURRENT_MATCH_REQUESTS)
+        .collect()
+        .await;
+
+    // Keep Riot's newest first order
+    for (slot, match_dto) in cached.iter_mut().filter(|slot| slot.is_none()).zip(fetched) {
+        *slot = match_dto;
+    }
+    let skipped = cached.iter().filter(|slot| slot.is_none()).count();
+    let matches: Vec<MatchDto> = cached.into_iter().flatten().collect();
+    // Cached matches are saved too, they may have been fetched for another player
+    store.save_matches(&puuid, &matches).await?;
+
+    Ok((matches, skipped))
+}
+
+/// Appended to a reply's description when `fetch_matches` skipped games
+pub fn skipped_games_line(skipped: usize) -> String {
+    match skipped {
+        0 => "".to_string(),
+        1 => "\n1 game could not be loaded".to_string(),
+        skipped => format!("\n{} games could not be loaded", skipped),
+    }
+}
+
+/// Match history field for one game. The outcome is `None` for remakes, which shouldn't
+/// count towards winrate
+pub fn get_match_info(
     match_resp: MatchDto,
     game_count: i32,
     player_puuid: String,
-) -> Result<((String, String, bool), bool), Box<dyn std::error::Error>> {
+    augments: &HashMap<i64, String>,
+) -> Result<(MatchField, Option<bool>), Box<dyn std::error::Error>> {
     let info = match_resp.info;
+    let score = performance_score(&info, &player_puuid)
+        .map(|score| format!(" - {}", score.label()))
+        .unwrap_or_default();
+    let timing = match_timing(&info, Utc::now().timestamp_millis());
     let InfoDto {
         participants,
         game_mode,
+        ..
     } = info;
     let participant_iter = participants.iter();
 
@@ -117,25 +248,83 @@ fn get_match_info(
     let me = participant_iter
         .clone()
         .find(|p| p.puuid == player_puuid)
-        .unwrap();
+        .ok_or("Player not found in match")?;
     let win = if me.win { "won" } else { "lost" };
     let me_kda = format!("{}/{}/{}", me.kills, me.deaths, me.assists);
 
-    if game_mode != "CLASSIC" {
+    if me.is_remake() {
+        return Ok((
+            (
+                format!("{}: {} (REMAKE)", game_count, game_mode),
+                format!("```({})\n{}\n\n{}```", me_kda, me.champion_name, timing),
+                true,
+            ),
+            None,
+        ));
+    }
+
+    if game_mode == "CHERRY" {
+        let partner = participant_iter
+            .clone()
+            .find(|p| p.player_subteam_id == me.player_subteam_id && p.puuid != me.puuid);
+        let augment_names = me
+            .augments()
+            .iter()
+            .map(|augment| {
+                augments
+                    .get(augment)
+                    .cloned()
+                    .unwrap_or_else(|| format!("Augment {}", augment))
+            })
+            .collect::<Vec<String>>();
+
+        let mut body = format!("({})\n{}", me_kda, me.champion_name);
+        if let Some(partner) = partner {
+            body.push_str(&format!(
+                "\nwith\n{}\n({})",
+                partner.champion_name, partner.riot_id_game_name
+            ));
+        }
+        if !augment_names.is_empty() {
+            body.push_str(&format!("\n\n{}", augment_names.join("\n")));
+        }
+        body.push_str(&format!("\n\n{}", timing));
+
         return Ok((
             (
-                format!("{}: {} ({})", game_count, game_mode, win.to_uppercase()),
-                format!("```({})\n{}```", me_kda, me.champion_name),
+                format!(
+                    "{}: ARENA ({})",
+                    game_count,
+                    ordinal(me.placement).to_uppercase()
+                ),
+                format!("```{}```", body),
                 true,
             ),
-            me.win,
+            Some(me.win),
         ));
     }
 
+    // Positions are empty outside of Summoner's Rift matchmade games, e.g. customs
     let opponent = participant_iter
         .clone()
-        .find(|p| p.team_id != me.team_id && p.team_position == me.team_position)
-        .unwrap();
+        .find(|p| p.team_id != me.team_id && p.team_position == me.team_position);
+    if game_mode != "CLASSIC" || me.team_position.is_empty() || opponent.is_none() {
+        return Ok((
+            (
+                format!(
+                    "{}: {} ({}){}",
+                    game_count,
+                    game_mode,
+                    win.to_uppercase(),
+                    score
+                ),
+                format!("```({})\n{}\n\n{}```", me_kda, me.champion_name, timing),
+                true,
+            ),
+            Some(me.win),
+        ));
+    }
+    let opponent = opponent.unwrap();
 
     let opponent_kda = format!(
         "{}/{}/{}",
@@ -143,21 +332,58 @@ fn get_match_info(
     );
     let output = (
         format!(
-            "{}: {} ({})",
+            "{}: {} ({}){}",
             game_count,
             me.team_position.to_uppercase(),
-            win.to_uppercase()
+            win.to_uppercase(),
+            score
         ),
         format!(
-            "```({})\n{}\nvs.\n({})\n{}\n({})```",
+            "```({})\n{}\nvs.\n({})\n{}\n({})\n\n{}```",
             me_kda,
             me.champion_name,
             opponent_kda,
             opponent.champion_name,
-            opponent.riot_id_game_name
+            opponent.riot_id_game_name,
+            timing
         ),
         true,
     );
 
-    Ok((output, me.win))
+    Ok((output, Some(me.win)))
+}
+
+/// Game length and how long ago it ended, e.g. `31:07 - 3h ago`
+fn match_timing(info: &InfoDto, now: i64) -> String {
+    // Older matches have no end timestamp
+    let ended = if info.game_end_timestamp > 0 {
+        info.game_end_timestamp
+    } else {
+        info.game_start_timestamp + info.game_duration * 1000
+    };
+    let minutes_ago = (now - ended).max(0) / 60_000;
+    let ago = match minutes_ago {
+        0 => "just now".to_string(),
+        1..=59 => format!("{}m ago", minutes_ago),
+        60..=1439 => format!("{}h ago", minutes_ago / 60),
+        _ => format!("{}d ago", minutes_ago / 1440),
+    };
+    format!(
+        "{}:{:02} - {}",
+        info.game_duration / 60,
+        info.game_duration % 60,
+        ago
+    )
+}
+
+/// Arena placement, e.g. `1st`
+fn ordinal(place: i32) -> String {
+    let suffix = match (place % 10, place % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{}{}", place, suffix)
 }
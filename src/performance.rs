@@ -0,0 +1,130 @@
+use crate::shared::types::{InfoDto, ParticipantDto};
+
+/// Weights of each input, summing to 1
+const KDA_WEIGHT: f64 = 0.25;
+const KILL_PARTICIPATION_WEIGHT: f64 = 0.2;
+const DAMAGE_SHARE_WEIGHT: f64 = 0.2;
+const GOLD_SHARE_WEIGHT: f64 = 0.1;
+const VISION_WEIGHT: f64 = 0.1;
+const OBJECTIVES_WEIGHT: f64 = 0.15;
+
+/// A participant's 0-10 score, with MVP for the best player on the winning team and ACE
+/// for the best one on the losing team
+#[derive(Debug, Clone, Copy)]
+pub struct PerformanceScore {
+    pub score: f64,
+    pub badge: Option<&'static str>,
+}
+
+impl PerformanceScore {
+    /// e.g. `7.8 MVP`
+    pub fn label(&self) -> String {
+        match self.badge {
+            Some(badge) => format!("{:.1} {}", self.score, badge),
+            None => format!("{:.1}", self.score),
+        }
+    }
+}
+
+/// Scores the player against everyone else in the game. Each input is scaled against the
+/// best value in the lobby, so 10 means best at everything. Arena and remakes aren't scored
+pub fn performance_score(info: &InfoDto, puuid: &str) -> Option<PerformanceScore> {
+    if info.game_mode == "CHERRY" || info.participants.len() < 2 {
+        return None;
+    }
+    let me_index = info.participants.iter().position(|p| p.puuid == puuid)?;
+    if info.participants[me_index].is_remake() {
+        return None;
+    }
+
+    let inputs: Vec<[f64; 6]> = info
+        .participants
+        .iter()
+        .map(|participant| score_inputs(participant, info))
+        .collect();
+    let mut best = [0.0; 6];
+    for input in inputs.iter() {
+        for (best, value) in best.iter_mut().zip(input) {
+            *best = f64::max(*best, *value);
+        }
+    }
+
+    let weights = [
+        KDA_WEIGHT,
+        KILL_PARTICIPATION_WEIGHT,
+        DAMAGE_SHARE_WEIGHT,
+        GOLD_SHARE_WEIGHT,
+        VISION_WEIGHT,
+        OBJECTIVES_WEIGHT,
+    ];
+    let scores: Vec<f64> = inputs
+        .iter()
+        .map(|input| {
+            input
+                .iter()
+                .zip(best.iter())
+                .zip(weights.iter())
+                .map(|((value, best), weight)| {
+                    if *best > 0.0 {
+                        value / best * weight
+                    } else {
+                        0.0
+                    }
+                })
+                .sum::<f64>()
+                * 10.0
+        })
+        .collect();
+
+    let me = &info.participants[me_index];
+    let team_best = info
+        .participants
+        .iter()
+        .zip(scores.iter())
+        .filter(|(participant, _)| participant.team_id == me.team_id)
+        .map(|(_, score)| *score)
+        .fold(f64::MIN, f64::max);
+    let badge = match (scores[me_index] >= team_best, me.win) {
+        (true, true) => Some("MVP"),
+        (true, false) => Some("ACE"),
+        (false, _) => None,
+    };
+
+    Some(PerformanceScore {
+        score: scores[me_index],
+        badge,
+    })
+}
+
+/// KDA, kill participation, damage share, gold share, vision per minute and objective damage
+fn score_inputs(participant: &ParticipantDto, info: &InfoDto) -> [f64; 6] {
+    let team = info
+        .participants
+        .iter()
+        .filter(|p| p.team_id == participant.team_id);
+    let share = |value: i32, total: i32| {
+        if total > 0 {
+            value as f64 / total as f64
+        } else {
+            0.0
+        }
+    };
+    let minutes = (info.game_duration as f64 / 60.0).max(1.0);
+
+    [
+        (participant.kills + participant.assists) as f64 / participant.deaths.max(1) as f64,
+        share(
+            participant.kills + participant.assists,
+            team.clone().map(|p| p.kills).sum(),
+        ),
+        share(
+            participant.total_damage_dealt_to_champions,
+            team.clone()
+                .map(|p| p.total_damage_dealt_to_champions)
+                .sum(),
+        ),
+        share(participant.gold_earned, team.map(|p| p.gold_earned).sum()),
+        participant.vision_score as f64 / minutes,
+        participant.damage_dealt_to_objectives as f64,
+    ]
+}
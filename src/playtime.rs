@@ -0,0 +1,273 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Duration, NaiveDate, Timelike, Utc};
+use plotters::prelude::*;
+use reqwest::Client;
+use serenity::all::Colour;
+
+use crate::{
+    matches::fetch_matches,
+    shared::{
+        charts::{encode_png, register_fonts, CHART_BACKGROUND, CHART_HEIGHT, CHART_WIDTH},
+        requests::request_for_puuid,
+        store::Store,
+        types::{AccountInfoContext, DiscordOutput, MatchDto, Platform},
+        util::RateLimiter,
+    },
+    stats::{find_participant, PerformanceTotals},
+};
+
+const CHART_BAR: RGBColor = RGBColor(88, 101, 242);
+/// Recent games fetched before reading the cache, so unlinked players have something to show
+const TOP_UP_MATCH_COUNT: i64 = 20;
+/// A longer gap between games ends the session
+const SESSION_GAP_MINUTES: i64 = 30;
+const MAX_DAY_ROWS: usize = 14;
+const TIMES_OF_DAY: [(&str, u32); 4] = [
+    ("Night", 0),
+    ("Morning", 6),
+    ("Afternoon", 12),
+    ("Evening", 18),
+];
+
+/// One game's place in time, in Unix milliseconds
+struct PlayedGame {
+    start: i64,
+    end: i64,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn handle_playtime_command(
+    player_name: &str,
+    tag: &str,
+    platform: Platform,
+    days: i64,
+    api_key: &str,
+    client: &Client,
+    store: &Store,
+    limiter: &RateLimiter,
+) -> Result<DiscordOutput, Box<dyn std::error::Error>> {
+    limiter.acquire().await;
+    let puuid = request_for_puuid(player_name, tag, platform, api_key, client).await?;
+
+    let account_info_context = AccountInfoContext {
+        puuid: puuid.clone(),
+        player_name: player_name.to_string(),
+        tag: tag.to_string(),
+        platform,
+    };
+    // Best effort, older games come from whatever the cache already has
+    let _ = fetch_matches(
+        TOP_UP_MATCH_COUNT,
+        api_key,
+        account_info_context,
+        client,
+        store,
+        limiter,
+    )
+    .await;
+
+    let since = Utc::now() - Duration::days(days);
+    let mut matches = store.cached_matches(&puuid, since.timestamp_millis()).await;
+    matches.sort_by_key(|match_dto| match_dto.info.game_start_timestamp);
+
+    let title = format!("{}#{}'s Playtime", player_name, tag);
+    let games: Vec<PlayedGame> = matches.iter().filter_map(played_game).collect();
+    if games.is_empty() {
+        return Ok(DiscordOutput::new(
+            Colour::LIGHT_GREY,
+            format!("No stored games in the last {} days", days),
+            vec![],
+            "".to_string(),
+            title,
+            "".to_string(),
+        ));
+    }
+
+    let total: i64 = games.iter().map(|game| game.end - game.start).sum();
+    let sessions = session_lengths(&games);
+    let average_session = sessions.iter().sum::<i64>() / sessions.len() as i64;
+
+    let mut per_day: BTreeMap<NaiveDate, (usize, i64)> = BTreeMap::new();
+    let mut per_hour = [0; 24];
+    for game in games.iter() {
+        let Some(started) = DateTime::from_timestamp_millis(game.start) else {
+            continue;
+        };
+        let day = per_day.entry(started.date_naive()).or_default();
+        day.0 += 1;
+        day.1 += game.end - game.start;
+        per_hour[started.hour() as usize] += 1;
+    }
+    let day_lines = per_day
+        .iter()
+        .rev()
+        .take(MAX_DAY_ROWS)
+        .map(|(day, (games, played))| {
+            format!(
+                "{} {:>3} games {:>7}",
+                day.format("%b %d"),
+                games,
+                format_hours(*played)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let fields = vec![
+        (
+            format!("Per Day - {} of {} days played", per_day.len(), days),
+            format!("```{}```", day_lines),
+            false,
+        ),
+        (
+            "Sessions".to_string(),
+            format!(
+                "{} sessions\n{} on average\n{} longest",
+                sessions.len(),
+                format_hours(average_session),
+                format_hours(*sessions.iter().max().unwrap())
+            ),
+            true,
+        ),
+        (
+            "Winrate by Time of Day".to_string(),
+            format!("```{}```", time_of_day_lines(&matches, &puuid)),
+            true,
+        ),
+    ];
+    let busiest_hour = (0..24).max_by_key(|hour| per_hour[*hour]).unwrap_or(0);
+    let chart = draw_hours_chart(&per_hour)?;
+
+    Ok(DiscordOutput::new(
+        Colour::BLURPLE,
+        format!(
+            "{} played over {} games in the last {} days, most often around {:02}:00.\nTimes \
+             are UTC, games count towards the hour they started in",
+            format_hours(total),
+            games.len(),
+            days,
+            busiest_hour
+        ),
+        fields,
+        "".to_string(),
+        title,
+        "".to_string(),
+    )
+    .with_attachment("playtime.png".to_string(), chart))
+}
+
+/// Older matches have no end timestamp, the duration fills in for it
+fn played_game(match_dto: &MatchDto) -> Option<PlayedGame> {
+    let info = &match_dto.info;
+    if info.game_start_timestamp <= 0 {
+        return None;
+    }
+    Some(PlayedGame {
+        start: info.game_start_timestamp,
+        end: info.game_start_timestamp + info.game_duration * 1000,
+    })
+}
+
+/// Milliseconds from the first game's start to the last game's end, for runs of games with
+/// less than `SESSION_GAP_MINUTES` between them. `games` are oldest first
+fn session_lengths(games: &[PlayedGame]) -> Vec<i64> {
+    let mut sessions = vec![];
+    let mut session_start = games[0].start;
+    for pair in games.windows(2) {
+        if pair[1].start - pair[0].end > SESSION_GAP_MINUTES * 60_000 {
+            sessions.push(pair[0].end - session_start);
+            session_start = pair[1].start;
+        }
+    }
+    sessions.push(games[games.len() - 1].end - session_start);
+    sessions
+}
+
+fn time_of_day_lines(matches: &[MatchDto], puuid: &str) -> String {
+    let mut totals: [PerformanceTotals; TIMES_OF_DAY.len()] =
+        std::array::from_fn(|_| PerformanceTotals::default());
+    for match_dto in matches {
+        let Some(me) = find_participant(match_dto, puuid) else {
+            continue;
+        };
+        let Some(started) = DateTime::from_timestamp_millis(match_dto.info.game_start_timestamp)
+        else {
+            continue;
+        };
+        let slot = TIMES_OF_DAY
+            .iter()
+            .rposition(|(_, from)| started.hour() >= *from)
+            .unwrap_or(0);
+        totals[slot].add(me, &match_dto.info);
+    }
+
+    TIMES_OF_DAY
+        .iter()
+        .zip(totals.iter())
+        .map(|((name, from), totals)| {
+            if totals.games == 0 {
+                format!("{:<9} {:02}-{:02}    -", name, from, from + 6)
+            } else {
+                format!(
+                    "{:<9} {:02}-{:02} {:>3.0}% ({})",
+                    name,
+                    from,
+                    from + 6,
+                    totals.winrate(),
+                    totals.games
+                )
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// e.g. `2h 05m`
+fn format_hours(millis: i64) -> String {
+    let minutes = millis / 60_000;
+    format!("{}h {:02}m", minutes / 60, minutes % 60)
+}
+
+/// Games started per hour of the day
+fn draw_hours_chart(per_hour: &[u32; 24]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    register_fonts();
+
+    let busiest = *per_hour.iter().max().unwrap_or(&0);
+    let mut buffer = vec![0; (CHART_WIDTH * CHART_HEIGHT * 3) as usize];
+    {
+        let root = BitMapBackend::with_buffer(&mut buffer, (CHART_WIDTH, CHART_HEIGHT))
+            .into_drawing_area();
+        root.fill(&CHART_BACKGROUND)?;
+
+        let mut chart = ChartBuilder::on(&root)
+            .margin(20)
+            .x_label_area_size(30)
+            .y_label_area_size(40)
+            .build_cartesian_2d(0.0..24.0, 0.0..(busiest + 1) as f64)?;
+
+        chart
+            .configure_mesh()
+            .x_labels(13)
+            .x_label_formatter(&|hour| format!("{:02}:00", *hour as u32))
+            .y_labels((busiest + 2).min(10) as usize)
+            .y_label_formatter(&|games| format!("{:.0}", games))
+            .label_style(("sans-serif", 14).into_font().color(&WHITE))
+            .axis_style(WHITE.mix(0.5))
+            .bold_line_style(WHITE.mix(0.15))
+            .light_line_style(TRANSPARENT)
+            .disable_x_mesh()
+            .draw()?;
+
+        chart.draw_series(per_hour.iter().enumerate().map(|(hour, games)| {
+            Rectangle::new(
+                [(hour as f64 + 0.1, 0.0), (hour as f64 + 0.9, *games as f64)],
+                CHART_BAR.filled(),
+            )
+        }))?;
+
+        root.present()?;
+    }
+
+    encode_png(buffer, CHART_WIDTH, CHART_HEIGHT)
+}
@@ -0,0 +1,196 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use chrono::Utc;
+use reqwest::Client;
+use serenity::all::{ChannelId, Colour, CreateMessage, Http};
+use tokio::time::sleep;
+use tracing::warn;
+
+use crate::shared::{
+    requests::request_league_entries,
+    store::{LinkedAccount, RankAlertSettings, Store},
+    types::{solo_queue_entry, DiscordOutput, LeagueEntryDto},
+    util::RateLimiter,
+};
+
+const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(600);
+
+type PollError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Background task started from `ready()`, snapshots linked players' solo queue ranks into
+/// the rank history and posts promotions, demotions and big LP swings in guilds with rank
+/// alerts turned on
+pub async fn poll_rank_changes(
+    http: Arc<Http>,
+    client: Client,
+    api_key: String,
+    store: Arc<Store>,
+    limiter: Arc<RateLimiter>,
+) {
+    loop {
+        if let Err(err) = announce_rank_changes(&http, &client, &api_key, &store, &limiter).await {
+            warn!("Polling rank changes failed: {}", err);
+        }
+        sleep(SNAPSHOT_INTERVAL).await;
+    }
+}
+
+async fn announce_rank_changes(
+    http: &Http,
+    client: &Client,
+    api_key: &str,
+    store: &Store,
+    limiter: &RateLimiter,
+) -> Result<(), PollError> {
+    let guilds: Vec<(u64, ChannelId, RankAlertSettings, Vec<LinkedAccount>)> = {
+        let data = store.read().await;
+        data.guild_settings
+            .iter()
+            .filter(|(_, settings)| settings.rank_alerts.enabled)
+            .filter_map(|(guild_id, settings)| {
+                Some((
+                    *guild_id,
+                    ChannelId::new(settings.announcement_channel?),
+                    settings.rank_alerts.clone(),
+                    data.guild_accounts(*guild_id)
+                        .into_iter()
+                        .map(|(_, account)| account.clone())
+                        .collect(),
+                ))
+            })
+            .collect()
+    };
+
+    // Every linked account is snapshotted for /lphistory, alerts then reuse the snapshots
+    let linked_accounts: Vec<LinkedAccount> = store
+        .read()
+        .await
+        .linked_accounts
+        .values()
+        .flatten()
+        .cloned()
+        .collect();
+    let mut snapshots: HashMap<String, Option<LeagueEntryDto>> = HashMap::new();
+    for account in linked_accounts {
+        if snapshots.contains_key(&account.puuid) {
+            continue;
+        }
+        limiter.acquire_background().await;
+        // One failed lookup only skips that account this round
+        let entries =
+            match request_league_entries(&account.puuid, account.platform, api_key, client).await {
+                Ok(entries) => entries,
+                Err(err) => {
+                    warn!(
+                        "Could not snapshot the rank of {}: {}",
+                        account.riot_id(),
+                        err
+                    );
+                    continue;
+                }
+            };
+        let entry = solo_queue_entry(&entries).cloned();
+        if let Some(entry) = &entry {
+            let timestamp = Utc::now().timestamp();
+            store
+                .update(|data| data.record_rank_snapshot(&account.puuid, timestamp, entry))
+                .await?;
+        }
+        snapshots.insert(account.puuid.clone(), entry);
+    }
+
+    for (guild_id, channel, rank_alerts, accounts) in guilds {
+        for account in accounts {
+            let Some(Some(current)) = snapshots.get(&account.puuid).cloned() else {
+                continue;
+            };
+
+            let baseline = store.read().await.guild_settings[&guild_id]
+                .rank_baselines
+                .get(&account.puuid)
+                .cloned();
+            let alert = match &baseline {
+                Some(baseline) => get_rank_alert(&account, baseline, &current, &rank_alerts),
+                None => None,
+            };
+
+            // Baseline moves on first sight and after every alert so swings aren't re-posted
+            if baseline.is_none() || alert.is_some() {
+                store
+                    .update(|data| {
+                        data.guild_settings
+                            .entry(guild_id)
+                            .or_default()
+                            .rank_baselines
+                            .insert(account.puuid.clone(), current.clone())
+                    })
+                    .await?;
+            }
+
+            if let Some(alert) = alert {
+                let message = CreateMessage::new().embed(alert.to_embed());
+                if let Err(err) = channel.send_message(http, message).await {
+                    warn!("Could not post rank alert in {}: {}", channel, err);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn get_rank_alert(
+    account: &LinkedAccount,
+    baseline: &LeagueEntryDto,
+    current: &LeagueEntryDto,
+    rank_alerts: &RankAlertSettings,
+) -> Option<DiscordOutput> {
+    let name = format!("{}#{}", account.player_name, account.tag);
+    let lp_change = current.absolute_league_points() - baseline.absolute_league_points();
+    let division_changed = current.tier != baseline.tier || current.rank != baseline.rank;
+
+    let (color, title, description) = if rank_alerts.divisions && division_changed {
+        if lp_change > 0 {
+            (
+                Colour::GOLD,
+                format!("🎉 {} promoted to {}", name, current.rank_string()),
+                format!("Up from {}, huge!", baseline.rank_string()),
+            )
+        } else {
+            (
+                Colour::DARK_RED,
+                format!("🫂 {} dropped to {}", name, current.rank_string()),
+                format!("Down from {}, it's only temporary", baseline.rank_string()),
+            )
+        }
+    } else if lp_change.abs() >= rank_alerts.lp_swing {
+        if lp_change > 0 {
+            (
+                Colour::DARK_GREEN,
+                format!("📈 {} is climbing", name),
+                format!("{:+} LP, now {}", lp_change, current.rank_string()),
+            )
+        } else {
+            (
+                Colour::DARK_RED,
+                format!("📉 {} is having a rough time", name),
+                format!(
+                    "{:+} LP, now {}. Hang in there",
+                    lp_change,
+                    current.rank_string()
+                ),
+            )
+        }
+    } else {
+        return None;
+    };
+
+    Some(DiscordOutput::new(
+        color,
+        description,
+        vec![],
+        "".to_string(),
+        title,
+        "".to_string(),
+    ))
+}
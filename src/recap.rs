@@ -0,0 +1,320 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use chrono::{DateTime, Datelike, Utc, Weekday};
+use reqwest::Client;
+use serenity::all::{ChannelId, Colour, CreateMessage, Http};
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+use crate::{
+    shared::{
+        requests::{request_match, request_match_ids, MatchIdQuery},
+        store::{LinkedAccount, Store},
+        types::DiscordOutput,
+        util::RateLimiter,
+    },
+    stats::{find_participant, PerformanceTotals},
+};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const RECAP_DAYS: i64 = 7;
+/// Most match ids looked up per player before the recap, enough to cover a busy week
+const RECAP_MATCH_COUNT: i64 = 100;
+/// Players need this many games to qualify for best and worst winrate
+const MIN_WINRATE_GAMES: u32 = 3;
+
+type PollError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Background task started from `ready()`, posts last week's recap every Monday in guilds
+/// that configured a recap channel
+pub async fn post_weekly_recaps(
+    http: Arc<Http>,
+    client: Client,
+    api_key: String,
+    store: Arc<Store>,
+    limiter: Arc<RateLimiter>,
+) {
+    loop {
+        let now = Utc::now();
+        if now.weekday() == Weekday::Mon {
+            post_due_recaps(&http, &client, &api_key, &store, &limiter, now).await;
+        }
+        sleep(CHECK_INTERVAL).await;
+    }
+}
+
+async fn post_due_recaps(
+    http: &Http,
+    client: &Client,
+    api_key: &str,
+    store: &Store,
+    limiter: &RateLimiter,
+    now: DateTime<Utc>,
+) {
+    let week = now.format("%G-W%V").to_string();
+    let due = {
+        let data = store.read().await;
+        data.guild_settings
+            .iter()
+            .filter(|(_, settings)| settings.last_recap_week.as_ref() != Some(&week))
+            .filter_map(|(guild_id, settings)| {
+                Some((
+                    *guild_id,
+                    ChannelId::new(settings.recap_channel?),
+                    data.guild_members(*guild_id)
+                        .into_iter()
+                        .map(|(user_id, accounts)| (user_id, accounts.to_vec()))
+                        .collect::<Vec<(u64, Vec<LinkedAccount>)>>(),
+                ))
+            })
+            .collect::<Vec<_>>()
+    };
+
+    for (guild_id, channel, members) in due {
+        // Top up the match cache first, announcements only cache games in guilds that use them
+        let since = now - chrono::Duration::days(RECAP_DAYS);
+        for account in members.iter().flat_map(|(_, accounts)| accounts) {
+            if let Err(err) = top_up_matches(account, since, api_key, client, store, limiter).await
+            {
+                warn!(
+                    "Could not refresh matches of {} for the recap: {}",
+                    account.riot_id(),
+                    err
+                );
+            }
+        }
+
+        let recap = get_weekly_recap(&members, store, now).await;
+        let message = CreateMessage::new().embed(recap.to_embed());
+        if let Err(err) = channel.send_message(http, message).await {
+            warn!("Could not post weekly recap in {}: {}", channel, err);
+            continue;
+        }
+
+        info!("Posted weekly recap {} in guild {}", week, guild_id);
+        let week = week.clone();
+        if let Err(err) = store
+            .update(|data| {
+                data.guild_settings
+                    .entry(guild_id)
+                    .or_default()
+                    .last_recap_week = Some(week)
+            })
+            .await
+        {
+            warn!("Could not save weekly recap state: {}", err);
+        }
+    }
+}
+
+/// Caches the account's games since `since` that aren't cached yet, one limited request at
+/// a time so recaps for many players don't burst through the dev key budget
+async fn top_up_matches(
+    account: &LinkedAccount,
+    since: DateTime<Utc>,
+    api_key: &str,
+    client: &Client,
+    store: &Store,
+    limiter: &RateLimiter,
+) -> Result<(), PollError> {
+    let query = MatchIdQuery {
+        count: RECAP_MATCH_COUNT,
+        start_time: Some(since.timestamp()),
+        ..MatchIdQuery::default()
+    };
+    limiter.acquire_background().await;
+    let match_ids =
+        request_match_ids(&account.puuid, account.platform, query, api_key, client).await?;
+
+    let mut matches = vec![];
+    for match_id in match_ids.iter() {
+        // Cached through another player, it only needs indexing under this one
+        match store.load_match(match_id) {
+            Some(match_dto) => matches.push(match_dto),
+            None => {
+                limiter.acquire_background().await;
+                match request_match(match_id, account.platform, api_key, client).await {
+                    Ok(match_dto) => matches.push(match_dto),
+                    Err(err) => warn!("Skipping match {} for the recap: {}", match_id, err),
+                }
+            }
+        }
+    }
+    store.save_matches(&account.puuid, &matches).await?;
+    Ok(())
+}
+
+/// Members with alts are summed up over all their accounts
+async fn get_weekly_recap(
+    members: &[(u64, Vec<LinkedAccount>)],
+    store: &Store,
+    now: DateTime<Utc>,
+) -> DiscordOutput {
+    let since = now - chrono::Duration::days(RECAP_DAYS);
+
+    let mut totals: Vec<(u64, PerformanceTotals)> = vec![];
+    let mut champions: HashMap<String, u32> = HashMap::new();
+    let mut loss_streaks: Vec<(u64, u32)> = vec![];
+    let mut lp_gains: Vec<(u64, i32)> = vec![];
+    for (user_id, accounts) in members {
+        let mut matches = vec![];
+        for account in accounts {
+            for match_dto in store
+                .cached_matches(&account.puuid, since.timestamp_millis())
+                .await
+            {
+                matches.push((match_dto, account.puuid.as_str()));
+            }
+        }
+        // Oldest first so streaks follow the order the games were played in
+        matches.sort_by_key(|(match_dto, _)| match_dto.info.game_end_timestamp);
+
+        let mut player_totals = PerformanceTotals::default();
+        let (mut streak, mut longest_streak) = (0, 0);
+        for (match_dto, puuid) in matches.iter() {
+            let Some(me) = find_participant(match_dto, puuid) else {
+                continue;
+            };
+            if me.is_remake() {
+                continue;
+            }
+            player_totals.add(me, &match_dto.info);
+            *champions.entry(me.champion_name.clone()).or_default() += 1;
+
+            streak = if me.win { 0 } else { streak + 1 };
+            longest_streak = longest_streak.max(streak);
+        }
+        if player_totals.games > 0 {
+            totals.push((*user_id, player_totals));
+            loss_streaks.push((*user_id, longest_streak));
+        }
+
+        // Measured from the last snapshot before the week started, or the first one in it
+        let data = store.read().await;
+        let mut lp_gain = None;
+        for account in accounts {
+            let history = data.rank_history.get(&account.puuid);
+            let baseline = history.and_then(|history| {
+                history
+                    .iter()
+                    .rev()
+                    .find(|snapshot| snapshot.timestamp <= since.timestamp())
+                    .or_else(|| history.first())
+            });
+            if let (Some(baseline), Some(latest)) = (baseline, history.and_then(|h| h.last())) {
+                if latest.timestamp > baseline.timestamp {
+                    *lp_gain.get_or_insert(0) += latest.entry.absolute_league_points()
+                        - baseline.entry.absolute_league_points();
+                }
+            }
+        }
+        if let Some(lp_gain) = lp_gain {
+            lp_gains.push((*user_id, lp_gain));
+        }
+    }
+
+    let title = format!(
+        "📅 Weekly Recap {} - {}",
+        since.format("%b %d"),
+        now.format("%b %d")
+    );
+    if totals.is_empty() {
+        return DiscordOutput::new(
+            Colour::LIGHT_GREY,
+            "Nobody played this week, touch grass achievement unlocked".to_string(),
+            vec![],
+            "".to_string(),
+            title,
+            "".to_string(),
+        );
+    }
+
+    let mut fields = vec![];
+    if let Some((user_id, most)) = totals.iter().max_by_key(|(_, t)| t.games) {
+        fields.push((
+            "🎮 Most games".to_string(),
+            format!("<@{}> - {} games", user_id, most.games),
+            true,
+        ));
+    }
+
+    let mut qualified: Vec<&(u64, PerformanceTotals)> = totals
+        .iter()
+        .filter(|(_, t)| t.games >= MIN_WINRATE_GAMES)
+        .collect();
+    qualified.sort_by(|(_, a), (_, b)| b.winrate().partial_cmp(&a.winrate()).unwrap());
+    if let Some((user_id, best)) = qualified.first() {
+        fields.push((
+            "🏆 Best winrate".to_string(),
+            format!(
+                "<@{}> - {:.0}% in {} games",
+                user_id,
+                best.winrate(),
+                best.games
+            ),
+            true,
+        ));
+    }
+    if let Some((user_id, worst)) = qualified.last().filter(|_| qualified.len() > 1) {
+        fields.push((
+            "💀 Worst winrate".to_string(),
+            format!(
+                "<@{}> - {:.0}% in {} games",
+                user_id,
+                worst.winrate(),
+                worst.games
+            ),
+            true,
+        ));
+    }
+
+    if let Some((champion, games)) = champions
+        .iter()
+        .max_by(|(a_name, a), (b_name, b)| a.cmp(b).then(b_name.cmp(a_name)))
+    {
+        fields.push((
+            "⭐ Most played champion".to_string(),
+            format!("{} - {} games", champion, games),
+            true,
+        ));
+    }
+
+    if let Some((user_id, gain)) = lp_gains
+        .iter()
+        .filter(|(_, gain)| *gain > 0)
+        .max_by_key(|(_, gain)| *gain)
+    {
+        fields.push((
+            "📈 Biggest LP gain".to_string(),
+            format!("<@{}> - {:+} LP", user_id, gain),
+            true,
+        ));
+    }
+
+    if let Some((user_id, streak)) = loss_streaks
+        .iter()
+        .filter(|(_, streak)| *streak > 1)
+        .max_by_key(|(_, streak)| *streak)
+    {
+        fields.push((
+            "🌧️ Longest loss streak".to_string(),
+            format!("<@{}> - {} in a row", user_id, streak),
+            true,
+        ));
+    }
+
+    let games: u32 = totals.iter().map(|(_, t)| t.games).sum();
+    DiscordOutput::new(
+        Colour::GOLD,
+        format!(
+            "{} games played by {} players over the past {} days",
+            games,
+            totals.len(),
+            RECAP_DAYS
+        ),
+        fields,
+        "".to_string(),
+        title,
+        "".to_string(),
+    )
+}
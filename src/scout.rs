@@ -0,0 +1,206 @@
+use futures::future::join_all;
+use reqwest::Client;
+use serenity::all::Colour;
+use tracing::warn;
+
+use crate::{
+    link::parse_riot_id,
+    shared::{
+        requests::{
+            request_for_puuid, request_league_entries, request_match, request_matches_from_puuid,
+        },
+        store::Store,
+        types::{solo_queue_entry, AccountInfoContext, DiscordOutput, MatchDto, Platform},
+        util::RateLimiter,
+    },
+    stats::{aggregate_by, current_streak, find_participant, PerformanceTotals},
+};
+
+const MAX_PLAYERS: usize = 5;
+const RECENT_MATCH_COUNT: i64 = 10;
+const TOP_CHAMPION_COUNT: usize = 3;
+/// What the client appends to names in the lobby and champ select chat
+const LOBBY_SUFFIXES: [&str; 3] = [" joined the lobby", " left the lobby", " joined the room"];
+
+pub async fn handle_scout_command(
+    text: &str,
+    platform: Platform,
+    api_key: &str,
+    client: &Client,
+    store: &Store,
+    limiter: &RateLimiter,
+) -> Result<DiscordOutput, Box<dyn std::error::Error>> {
+    let riot_ids = parse_lobby(text);
+    if riot_ids.is_empty() {
+        return Err("No Riot IDs found, paste lines like `Name#Tag joined the lobby`".into());
+    }
+
+    let player_futures = riot_ids.iter().map(|(player_name, tag)| async move {
+        scout_player(player_name, tag, platform, api_key, client, store, limiter)
+            .await
+            .unwrap_or_else(|err| format!("Could not scout: {}", err))
+    });
+    let summaries = join_all(player_futures).await;
+
+    let fields = riot_ids
+        .iter()
+        .zip(summaries)
+        .map(|((player_name, tag), summary)| (format!("{}#{}", player_name, tag), summary, false))
+        .collect();
+
+    Ok(DiscordOutput::new(
+        Colour::BLURPLE,
+        format!("Last {} games per player", RECENT_MATCH_COUNT),
+        fields,
+        "".to_string(),
+        "🔍 Lobby Scout".to_string(),
+        "".to_string(),
+    ))
+}
+
+/// Pulls up to 5 unique Riot IDs out of pasted lobby lines, or a comma separated list.
+/// Slash command options flatten newlines, so the lobby suffixes double as separators
+fn parse_lobby(text: &str) -> Vec<(String, String)> {
+    let text = LOBBY_SUFFIXES
+        .iter()
+        .fold(text.to_string(), |text, suffix| text.replace(suffix, "\n"));
+
+    let mut riot_ids: Vec<(String, String)> = vec![];
+    for line in text.split(['\n', ',']) {
+        let Some((player_name, tag)) = parse_riot_id(line) else {
+            continue;
+        };
+        let already_found = riot_ids.iter().any(|(name, found_tag)| {
+            name.eq_ignore_ascii_case(player_name) && found_tag.eq_ignore_ascii_case(tag)
+        });
+        if !already_found {
+            riot_ids.push((player_name.to_string(), tag.to_string()));
+        }
+    }
+    riot_ids.truncate(MAX_PLAYERS);
+    riot_ids
+}
+
+/// Rank, recent winrate, streak and top champions on a few lines. Every Riot call goes
+/// through the limiter since five players fan out into dozens of requests
+async fn scout_player(
+    player_name: &str,
+    tag: &str,
+    platform: Platform,
+    api_key: &str,
+    client: &Client,
+    store: &Store,
+    limiter: &RateLimiter,
+) -> Result<String, String> {
+    limiter.acquire().await;
+    let puuid = request_for_puuid(player_name, tag, platform, api_key, client)
+        .await
+        .map_err(|_| "player not found".to_string())?;
+
+    limiter.acquire().await;
+    let entries = request_league_entries(&puuid, platform, api_key, client)
+        .await
+        .map_err(|err| err.to_string())?;
+    let rank = match solo_queue_entry(&entries) {
+        Some(entry) => format!(
+            "{} ({:.0}% of {})",
+            entry.rank_string(),
+            entry.wins as f64 / (entry.wins + entry.losses).max(1) as f64 * 100.0,
+            entry.wins + entry.losses
+        ),
+        None => "Unranked".to_string(),
+    };
+
+    let matches = fetch_recent_matches(
+        &puuid,
+        player_name,
+        tag,
+        platform,
+        api_key,
+        client,
+        store,
+        limiter,
+    )
+    .await?;
+    let mut totals = PerformanceTotals::default();
+    let mut outcomes = vec![];
+    for match_dto in matches.iter() {
+        if let Some(me) = find_participant(match_dto, &puuid).filter(|me| !me.is_remake()) {
+            totals.add(me, &match_dto.info);
+            outcomes.push(me.win);
+        }
+    }
+    if totals.games == 0 {
+        return Ok(format!("{}\nNo recent games", rank));
+    }
+
+    let top_champions = aggregate_by(&matches, &puuid, |me, _| me.champion_name.clone())
+        .into_iter()
+        .take(TOP_CHAMPION_COUNT)
+        .map(|(champion, totals)| format!("{} {}", champion, totals.games))
+        .collect::<Vec<String>>()
+        .join(", ");
+
+    Ok(format!(
+        "{}\n{:.0}% WR - {:.2} KDA - streak {}\n{}",
+        rank,
+        totals.winrate(),
+        totals.kda(),
+        streak(&outcomes),
+        top_champions
+    ))
+}
+
+/// Like `fetch_matches` but one limited request at a time, cached matches cost nothing
+#[allow(clippy::too_many_arguments)]
+async fn fetch_recent_matches(
+    puuid: &str,
+    player_name: &str,
+    tag: &str,
+    platform: Platform,
+    api_key: &str,
+    client: &Client,
+    store: &Store,
+    limiter: &RateLimiter,
+) -> Result<Vec<MatchDto>, String> {
+    let account_info_context = AccountInfoContext {
+        puuid: puuid.to_string(),
+        player_name: player_name.to_string(),
+        tag: tag.to_string(),
+        platform,
+    };
+    limiter.acquire().await;
+    let match_ids =
+        request_matches_from_puuid(RECENT_MATCH_COUNT, api_key, account_info_context, client)
+            .await
+            .map_err(|err| err.to_string())?;
+
+    let mut matches = vec![];
+    let mut fetched = vec![];
+    for match_id in match_ids.iter() {
+        match store.load_match(match_id) {
+            Some(match_dto) => matches.push(match_dto),
+            None => {
+                limiter.acquire().await;
+                if let Ok(match_dto) = request_match(match_id, platform, api_key, client).await {
+                    fetched.push(match_dto);
+                }
+            }
+        }
+    }
+    if let Err(err) = store.save_matches(puuid, &fetched).await {
+        warn!("Could not cache scouted matches: {}", err);
+    }
+
+    matches.extend(fetched);
+    matches.sort_by_key(|match_dto| std::cmp::Reverse(match_dto.info.game_end_timestamp));
+    Ok(matches)
+}
+
+/// Current run of wins or losses, newest game first, e.g. `W3`
+fn streak(outcomes: &[bool]) -> String {
+    match current_streak(outcomes) {
+        Some((win, length)) => format!("{}{}", if win { "W" } else { "L" }, length),
+        None => "-".to_string(),
+    }
+}
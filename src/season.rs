@@ -0,0 +1,348 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+
+use chrono::{DateTime, Datelike, TimeZone, Utc};
+use reqwest::Client;
+use serenity::all::Colour;
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+use crate::{
+    shared::{
+        requests::{request_for_puuid, request_match, request_match_ids, MatchIdQuery},
+        store::{LinkedAccount, SeasonBackfill, Store},
+        types::{DiscordOutput, MatchDto, Platform},
+        util::RateLimiter,
+    },
+    stats::{aggregate_by, find_participant, role_name, stats_table, PerformanceTotals},
+};
+
+const BACKFILL_INTERVAL: Duration = Duration::from_secs(15 * 60);
+/// Most match ids match-v5 returns per request
+const MATCH_ID_PAGE_SIZE: i64 = 100;
+/// Players looked up with /season who aren't linked are kept loaded this long
+const LOOKUP_EXPIRY: Duration = Duration::from_secs(14 * 24 * 60 * 60);
+/// Walks overlap the previous one by this much, games still running when it started are
+/// only listed once they end
+const SYNC_OVERLAP_SECONDS: i64 = 60 * 60;
+
+type PollError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Ranked seasons start in early January, so the calendar year is close enough and does not
+/// need updating every year
+fn season_start(now: DateTime<Utc>) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(now.year(), 1, 1, 0, 0, 0).unwrap()
+}
+
+/// Background task started from `ready()`, walks the season's match history of linked players
+/// and players recently looked up with /season into the match cache, then keeps it topped up
+pub async fn backfill_seasons(
+    client: Client,
+    api_key: String,
+    store: Arc<Store>,
+    limiter: Arc<RateLimiter>,
+) {
+    loop {
+        backfill_due_seasons(&client, &api_key, &store, &limiter).await;
+        sleep(BACKFILL_INTERVAL).await;
+    }
+}
+
+async fn backfill_due_seasons(
+    client: &Client,
+    api_key: &str,
+    store: &Store,
+    limiter: &RateLimiter,
+) {
+    let now = Utc::now();
+    let season = now.year();
+
+    let backfills = store
+        .update(|data| {
+            let linked: HashSet<String> = data
+                .linked_accounts
+                .values()
+                .flatten()
+                .map(|account| account.puuid.clone())
+                .collect();
+            // Unlinked accounts and players nobody looked up in a while stop being polled
+            data.season_backfills.retain(|puuid, backfill| {
+                linked.contains(puuid)
+                    || backfill.looked_up.is_some_and(|looked_up| {
+                        now.timestamp() - looked_up < LOOKUP_EXPIRY.as_secs() as i64
+                    })
+            });
+            for account in data.linked_accounts.values().flatten() {
+                data.season_backfills
+                    .entry(account.puuid.clone())
+                    .or_insert_with(|| SeasonBackfill {
+                        account: account.clone(),
+                        season,
+                        synced_until: None,
+                        looked_up: None,
+                    });
+            }
+            data.season_backfills
+                .values()
+                .cloned()
+                .collect::<Vec<SeasonBackfill>>()
+        })
+        .await;
+    let backfills = match backfills {
+        Ok(backfills) => backfills,
+        Err(err) => {
+            warn!("Could not load season progress: {}", err);
+            return;
+        }
+    };
+
+    for backfill in backfills {
+        let account = &backfill.account;
+        // A finished season only needs the games played since the last walk. The match cache
+        // can't tell, commands add recent games to it without walking what came before
+        let start_time = match backfill.synced_until {
+            Some(synced_until) if backfill.season == season => {
+                (synced_until - SYNC_OVERLAP_SECONDS).max(season_start(now).timestamp())
+            }
+            _ => season_start(now).timestamp(),
+        };
+
+        let walk_started = Utc::now().timestamp();
+        match walk_match_history(account, start_time, api_key, client, store, limiter).await {
+            Ok(fetched) => {
+                if fetched > 0 {
+                    info!("Cached {} season matches of {}", fetched, account.riot_id());
+                }
+                // Accounts unlinked in the meantime stay dropped
+                let result = store
+                    .update(|data| {
+                        if let Some(backfill) = data.season_backfills.get_mut(&account.puuid) {
+                            backfill.season = season;
+                            backfill.synced_until = Some(walk_started);
+                        }
+                    })
+                    .await;
+                if let Err(err) = result {
+                    warn!("Could not save season progress: {}", err);
+                }
+            }
+            // Whatever was cached stays cached, the next round picks up where this one failed
+            Err(err) => warn!(
+                "Loading the season of {} failed: {}",
+                account.riot_id(),
+                err
+            ),
+        }
+    }
+}
+
+/// Pages through every match id since `start_time`, fetching the ones not cached yet.
+/// Returns how many matches were fetched
+async fn walk_match_history(
+    account: &LinkedAccount,
+    start_time: i64,
+    api_key: &str,
+    client: &Client,
+    store: &Store,
+    limiter: &RateLimiter,
+) -> Result<usize, PollError> {
+    let mut fetched = 0;
+    let mut start = 0;
+    loop {
+        limiter.acquire_background().await;
+        let query = MatchIdQuery {
+            start,
+            count: MATCH_ID_PAGE_SIZE,
+            start_time: Some(start_time),
+            end_time: None,
+        };
+        let match_ids =
+            request_match_ids(&account.puuid, account.platform, query, api_key, client).await?;
+
+        // Saved once per page, every save rewrites the whole store
+        let mut page = vec![];
+        for match_id in match_ids.iter() {
+            // Cached through another player, it only needs indexing under this one
+            match store.load_match(match_id) {
+                Some(match_dto) => page.push(match_dto),
+                None => {
+                    limiter.acquire_background().await;
+                    match request_match(match_id, account.platform, api_key, client).await {
+                        Ok(match_dto) => {
+                            fetched += 1;
+                            page.push(match_dto);
+                        }
+                        Err(err) => {
+                            // Keep what this page already fetched
+                            store.save_matches(&account.puuid, &page).await?;
+                            return Err(err.into());
+                        }
+                    }
+                }
+            }
+        }
+        store.save_matches(&account.puuid, &page).await?;
+
+        if (match_ids.len() as i64) < MATCH_ID_PAGE_SIZE {
+            return Ok(fetched);
+        }
+        start += MATCH_ID_PAGE_SIZE;
+    }
+}
+
+pub async fn handle_season_command(
+    player_name: &str,
+    tag: &str,
+    platform: Platform,
+    api_key: &str,
+    client: &Client,
+    store: &Store,
+    limiter: &RateLimiter,
+) -> Result<DiscordOutput, Box<dyn std::error::Error>> {
+    limiter.acquire().await;
+    let puuid = request_for_puuid(player_name, tag, platform, api_key, client).await?;
+    let now = Utc::now();
+    let season = now.year();
+
+    // Unlinked players are queued for the backfill task the first time they are looked up
+    let synced = store
+        .update(|data| {
+            let backfill = data
+                .season_backfills
+                .entry(puuid.clone())
+                .or_insert_with(|| SeasonBackfill {
+                    account: LinkedAccount {
+                        puuid: puuid.clone(),
+                        player_name: player_name.to_string(),
+                        tag: tag.to_string(),
+                        platform,
+                    },
+                    season,
+                    synced_until: None,
+                    looked_up: None,
+                });
+            backfill.looked_up = Some(now.timestamp());
+            if backfill.season != season {
+                backfill.season = season;
+                backfill.synced_until = None;
+            }
+            backfill.synced_until.is_some()
+        })
+        .await?;
+
+    let matches = store
+        .cached_matches(&puuid, season_start(now).timestamp_millis())
+        .await;
+    let title = format!("{}#{}'s Season {}", player_name, tag, season);
+    let loading_note = if synced {
+        "".to_string()
+    } else {
+        format!(
+            "\n⏳ Still loading the season in the background, {} games so far. Check back later",
+            matches.len()
+        )
+    };
+
+    let mut overall = PerformanceTotals::default();
+    for match_dto in matches.iter() {
+        if let Some(me) = find_participant(match_dto, &puuid) {
+            overall.add(me, &match_dto.info);
+        }
+    }
+    if overall.games == 0 {
+        return Ok(DiscordOutput::new(
+            Colour::LIGHT_GREY,
+            format!("No games this season yet{}", loading_note),
+            vec![],
+            "".to_string(),
+            title,
+            "".to_string(),
+        ));
+    }
+
+    let champions = aggregate_by(&matches, &puuid, |me, _| me.champion_name.clone());
+    let roles = aggregate_by(&matches, &puuid, role_name);
+    let role_lines = roles
+        .iter()
+        .map(|(role, totals)| {
+            format!(
+                "{:<8}{:>4.0}%{:>5} games {:>4.0}% WR",
+                role,
+                totals.games as f64 / overall.games as f64 * 100.0,
+                totals.games,
+                totals.winrate()
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let fields = vec![
+        (
+            "Months".to_string(),
+            format!("```{}```", monthly_lines(&matches, &puuid)),
+            false,
+        ),
+        (
+            format!("Champion Pool - {} played", champions.len()),
+            format!("```{}```", stats_table(&champions)),
+            false,
+        ),
+        ("Roles".to_string(), format!("```{}```", role_lines), false),
+    ];
+
+    Ok(DiscordOutput::new(
+        if overall.winrate() >= 50.0 {
+            Colour::DARK_GREEN
+        } else {
+            Colour::DARK_RED
+        },
+        format!(
+            "{} games - {:.0}% WR - {:.2} KDA since {}{}",
+            overall.games,
+            overall.winrate(),
+            overall.kda(),
+            season_start(now).format("%b %d"),
+            loading_note
+        ),
+        fields,
+        "".to_string(),
+        title,
+        "".to_string(),
+    ))
+}
+
+/// Games and winrate per month, oldest first
+fn monthly_lines(matches: &[MatchDto], puuid: &str) -> String {
+    let mut months: BTreeMap<u32, (String, PerformanceTotals)> = BTreeMap::new();
+    for match_dto in matches {
+        let Some(me) = find_participant(match_dto, puuid) else {
+            continue;
+        };
+        let Some(ended) = DateTime::from_timestamp_millis(match_dto.info.game_end_timestamp) else {
+            continue;
+        };
+        months
+            .entry(ended.month())
+            .or_insert_with(|| (ended.format("%b").to_string(), PerformanceTotals::default()))
+            .1
+            .add(me, &match_dto.info);
+    }
+
+    months
+        .values()
+        .filter(|(_, totals)| totals.games > 0)
+        .map(|(month, totals)| {
+            format!(
+                "{} {:>4} games {:>4.0}% WR {:>5.2} KDA",
+                month,
+                totals.games,
+                totals.winrate(),
+                totals.kda()
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
@@ -0,0 +1,199 @@
+use serenity::all::{ChannelId, Colour, CommandDataOption, CommandDataOptionValue, GuildId};
+
+use crate::shared::{store::Store, types::DiscordOutput};
+
+pub async fn handle_settings_command(
+    guild_id: GuildId,
+    subcommand: &CommandDataOption,
+    store: &Store,
+) -> Result<DiscordOutput, Box<dyn std::error::Error>> {
+    let options = match &subcommand.value {
+        CommandDataOptionValue::SubCommand(options) => options,
+        _ => return Err("Expected a subcommand".into()),
+    };
+    let iter = options.iter();
+
+    match subcommand.name.as_str() {
+        "announcements" => {
+            let channel = iter
+                .clone()
+                .find(|opt| opt.name == "channel")
+                .and_then(|opt| opt.value.as_channel_id());
+
+            // Tilt alerts have nowhere else to go, so they are switched off with the channel
+            let tilt_alerts_off = store
+                .update(|data| {
+                    let settings = data.guild_settings.entry(guild_id.get()).or_default();
+                    settings.announcement_channel = channel.map(ChannelId::get);
+                    let tilt_alerts_off = channel.is_none() && settings.tilt_alerts.enabled;
+                    if tilt_alerts_off {
+                        settings.tilt_alerts.enabled = false;
+                    }
+                    tilt_alerts_off
+                })
+                .await?;
+
+            Ok(settings_output(match (channel, tilt_alerts_off) {
+                (Some(channel), _) => {
+                    format!("Finished games will be posted in <#{}>", channel)
+                }
+                (None, true) => {
+                    "Finished game announcements and tilt alerts turned off".to_string()
+                }
+                (None, false) => "Finished game announcements turned off".to_string(),
+            }))
+        }
+        "recap" => {
+            let channel = iter
+                .clone()
+                .find(|opt| opt.name == "channel")
+                .and_then(|opt| opt.value.as_channel_id());
+
+            store
+                .update(|data| {
+                    data.guild_settings
+                        .entry(guild_id.get())
+                        .or_default()
+                        .recap_channel = channel.map(ChannelId::get)
+                })
+                .await?;
+
+            Ok(settings_output(match channel {
+                Some(channel) => format!(
+                    "The weekly recap will be posted in <#{}> on Mondays",
+                    channel
+                ),
+                None => "Weekly recap turned off".to_string(),
+            }))
+        }
+        "live_games" => {
+            let enabled = iter
+                .clone()
+                .find(|opt| opt.name == "enabled")
+                .and_then(|opt| opt.value.as_bool())
+                .unwrap();
+
+            let has_channel = store
+                .update(|data| {
+                    let settings = data.guild_settings.entry(guild_id.get()).or_default();
+                    settings.live_games = enabled;
+                    settings.announcement_channel.is_some()
+                })
+                .await?;
+
+            Ok(settings_output(match (enabled, has_channel) {
+                (true, true) => {
+                    "Linked players starting a game will be posted in the announcement channel"
+                        .to_string()
+                }
+                (true, false) => {
+                    "Live games turned on, set a channel with /settings announcements to see them"
+                        .to_string()
+                }
+                (false, _) => "Live game notifications turned off".to_string(),
+            }))
+        }
+        "rank_alerts" => {
+            let enabled = iter
+                .clone()
+                .find(|opt| opt.name == "enabled")
+                .and_then(|opt| opt.value.as_bool())
+                .unwrap();
+            let lp_swing = iter
+                .clone()
+                .find(|opt| opt.name == "lp_swing")
+                .and_then(|opt| opt.value.as_i64());
+            let divisions = iter
+                .clone()
+                .find(|opt| opt.name == "divisions")
+                .and_then(|opt| opt.value.as_bool());
+
+            let rank_alerts = store
+                .update(|data| {
+                    let settings = data.guild_settings.entry(guild_id.get()).or_default();
+                    settings.rank_alerts.enabled = enabled;
+                    if let Some(lp_swing) = lp_swing {
+                        settings.rank_alerts.lp_swing = lp_swing as i32;
+                    }
+                    if let Some(divisions) = divisions {
+                        settings.rank_alerts.divisions = divisions;
+                    }
+                    settings.rank_alerts.clone()
+                })
+                .await?;
+
+            Ok(settings_output(if rank_alerts.enabled {
+                format!(
+                    "Rank alerts on: swings of {}+ LP{}",
+                    rank_alerts.lp_swing,
+                    if rank_alerts.divisions {
+                        ", promotions and demotions"
+                    } else {
+                        ""
+                    }
+                )
+            } else {
+                "Rank alerts turned off".to_string()
+            }))
+        }
+        "tilt_alerts" => {
+            let enabled = iter
+                .clone()
+                .find(|opt| opt.name == "enabled")
+                .and_then(|opt| opt.value.as_bool())
+                .unwrap();
+            let losses = iter
+                .clone()
+                .find(|opt| opt.name == "losses")
+                .and_then(|opt| opt.value.as_i64());
+            let hours = iter
+                .clone()
+                .find(|opt| opt.name == "hours")
+                .and_then(|opt| opt.value.as_i64());
+
+            // Tilt alerts are posted in the announcement channel, without one they'd never show
+            let tilt_alerts = store
+                .update(|data| {
+                    let settings = data.guild_settings.entry(guild_id.get()).or_default();
+                    if enabled && settings.announcement_channel.is_none() {
+                        return None;
+                    }
+                    settings.tilt_alerts.enabled = enabled;
+                    if let Some(losses) = losses {
+                        settings.tilt_alerts.losses = losses as usize;
+                    }
+                    if let Some(hours) = hours {
+                        settings.tilt_alerts.window_hours = hours;
+                    }
+                    Some(settings.tilt_alerts.clone())
+                })
+                .await?
+                .ok_or(
+                    "Tilt alerts are posted in the announcement channel, set one with \
+                     /settings announcements first",
+                )?;
+
+            Ok(settings_output(if tilt_alerts.enabled {
+                format!(
+                    "Linked players losing {} ranked games in a row within {} hours will be \
+                     told to take a break in the announcement channel",
+                    tilt_alerts.losses, tilt_alerts.window_hours
+                )
+            } else {
+                "Tilt alerts turned off".to_string()
+            }))
+        }
+        subcommand => Err(format!("Unknown settings subcommand {}", subcommand).into()),
+    }
+}
+
+fn settings_output(description: String) -> DiscordOutput {
+    DiscordOutput::new(
+        Colour::DARK_GREEN,
+        description,
+        vec![],
+        "".to_string(),
+        "Settings updated".to_string(),
+        "".to_string(),
+    )
+}
@@ -0,0 +1,35 @@
+use std::{io::Cursor, sync::Once};
+
+use image::{ImageOutputFormat, RgbImage};
+use plotters::style::{register_font, FontStyle, RGBColor};
+use tracing::warn;
+
+pub const CHART_WIDTH: u32 = 800;
+pub const CHART_HEIGHT: u32 = 400;
+/// Discord's dark theme, so charts blend into the embed
+pub const CHART_BACKGROUND: RGBColor = RGBColor(47, 49, 54);
+
+/// Charts render without system fonts, so the font ships with the bot
+static FONT: &[u8] = include_bytes!("../../assets/fonts/DejaVuSans.ttf");
+static REGISTER_FONT: Once = Once::new();
+
+/// Must run before drawing any text with plotters
+pub fn register_fonts() {
+    REGISTER_FONT.call_once(|| {
+        if register_font("sans-serif", FontStyle::Normal, FONT).is_err() {
+            warn!("Could not register chart font");
+        }
+    });
+}
+
+/// Encodes the RGB buffer a plotters `BitMapBackend` drew into as a PNG
+pub fn encode_png(
+    buffer: Vec<u8>,
+    width: u32,
+    height: u32,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let image = RgbImage::from_raw(width, height, buffer).ok_or("Chart buffer has wrong size")?;
+    let mut png = Cursor::new(Vec::new());
+    image.write_to(&mut png, ImageOutputFormat::Png)?;
+    Ok(png.into_inner())
+}
@@ -1,3 +1,6 @@
+pub mod charts;
 pub mod requests;
+pub mod static_data;
+pub mod store;
 pub mod types;
 pub mod util;
@@ -1,8 +1,11 @@
 use super::{
-    types::{AccountDto, AccountInfoContext},
+    types::{
+        AccountDto, AccountInfoContext, ChampionMasteryDto, CurrentGameInfo, LeagueEntryDto,
+        MatchDto, Platform, TimelineDto,
+    },
     util::retry_requests,
 };
-use reqwest::{Client, Error, Response};
+use reqwest::{Client, Error, Response, StatusCode};
 
 pub async fn send_request(
     url: &str,
@@ -20,20 +23,24 @@ pub async fn send_request(
 pub async fn request_for_puuid(
     player_name: &str,
     tag: &str,
-    region: &str,
+    platform: Platform,
     api_key: &str,
     client: &Client,
 ) -> Result<String, Error> {
     let account_url = format!(
-        "https://{}.api.riotgames.com/riot/account/v1/accounts/by-riot-id/{}/{}",
-        region, player_name, tag
+        "{}/riot/account/v1/accounts/by-riot-id/{}/{}",
+        platform.account_region().host(),
+        player_name,
+        tag
     );
 
-    let response = send_request(account_url.as_str(), Some(api_key), client)
-        .await
-        .unwrap();
+    let response = send_request(account_url.as_str(), Some(api_key), client).await?;
 
-    let puuid = response.json::<AccountDto>().await.unwrap().puuid;
+    let puuid = response
+        .error_for_status()?
+        .json::<AccountDto>()
+        .await?
+        .puuid;
 
     return Ok(puuid);
 }
@@ -44,15 +51,178 @@ pub async fn request_matches_from_puuid(
     account_info_context: AccountInfoContext,
     client: &Client,
 ) -> Result<Vec<String>, Error> {
-    let AccountInfoContext { region, puuid, .. } = account_info_context;
-    let matches_from_puuid_url = format!(
-        "https://{}.api.riotgames.com/lol/match/v5/matches/by-puuid/{}/ids?count={}",
-        region, puuid, game_count
+    let query = MatchIdQuery {
+        count: game_count,
+        ..Default::default()
+    };
+    request_match_ids(
+        &account_info_context.puuid,
+        account_info_context.platform,
+        query,
+        api_key,
+        client,
+    )
+    .await
+}
+
+/// Query parameters of match-v5 `by-puuid/{puuid}/ids`, newest matches come first
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MatchIdQuery {
+    /// Offset into the player's history
+    pub start: i64,
+    /// At most 100 per request
+    pub count: i64,
+    /// Unix seconds
+    pub start_time: Option<i64>,
+    /// Unix seconds
+    pub end_time: Option<i64>,
+}
+
+pub async fn request_match_ids(
+    puuid: &str,
+    platform: Platform,
+    query: MatchIdQuery,
+    api_key: &str,
+    client: &Client,
+) -> Result<Vec<String>, Error> {
+    let mut match_ids_url = format!(
+        "{}/lol/match/v5/matches/by-puuid/{}/ids?start={}&count={}",
+        platform.region().host(),
+        puuid,
+        query.start,
+        query.count
+    );
+    if let Some(start_time) = query.start_time {
+        match_ids_url.push_str(&format!("&startTime={}", start_time));
+    }
+    if let Some(end_time) = query.end_time {
+        match_ids_url.push_str(&format!("&endTime={}", end_time));
+    }
+
+    let response = send_request(match_ids_url.as_str(), Some(api_key), client).await?;
+    response.error_for_status()?.json::<Vec<String>>().await
+}
+
+pub async fn request_match(
+    match_id: &str,
+    platform: Platform,
+    api_key: &str,
+    client: &Client,
+) -> Result<MatchDto, Error> {
+    let match_url = format!(
+        "{}/lol/match/v5/matches/{}",
+        platform.region().host(),
+        match_id
     );
 
-    let response = send_request(matches_from_puuid_url.as_str(), Some(api_key), client)
+    let response = send_request(match_url.as_str(), Some(api_key), client).await?;
+    response.error_for_status()?.json::<MatchDto>().await
+}
+
+pub async fn request_timeline(
+    match_id: &str,
+    platform: Platform,
+    api_key: &str,
+    client: &Client,
+) -> Result<TimelineDto, Error> {
+    let timeline_url = format!(
+        "{}/lol/match/v5/matches/{}/timeline",
+        platform.region().host(),
+        match_id
+    );
+
+    let response = send_request(timeline_url.as_str(), Some(api_key), client).await?;
+    response.error_for_status()?.json::<TimelineDto>().await
+}
+
+/// Returns `None` when the player is not currently in a game
+pub async fn request_active_game(
+    puuid: &str,
+    platform: Platform,
+    api_key: &str,
+    client: &Client,
+) -> Result<Option<CurrentGameInfo>, Error> {
+    let active_game_url = format!(
+        "{}/lol/spectator/v5/active-games/by-summoner/{}",
+        platform.host(),
+        puuid
+    );
+
+    let response = send_request(active_game_url.as_str(), Some(api_key), client).await?;
+    if response.status() == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+
+    let game = response
+        .error_for_status()?
+        .json::<CurrentGameInfo>()
+        .await?;
+    Ok(Some(game))
+}
+
+pub async fn request_league_entries(
+    puuid: &str,
+    platform: Platform,
+    api_key: &str,
+    client: &Client,
+) -> Result<Vec<LeagueEntryDto>, Error> {
+    let league_entries_url = format!(
+        "{}/lol/league/v4/entries/by-puuid/{}",
+        platform.host(),
+        puuid
+    );
+
+    let response = send_request(league_entries_url.as_str(), Some(api_key), client).await?;
+    response
+        .error_for_status()?
+        .json::<Vec<LeagueEntryDto>>()
         .await
-        .unwrap();
-    let match_ids = response.json::<Vec<String>>().await.unwrap();
-    return Ok(match_ids);
+}
+
+pub async fn request_top_masteries(
+    puuid: &str,
+    count: i64,
+    platform: Platform,
+    api_key: &str,
+    client: &Client,
+) -> Result<Vec<ChampionMasteryDto>, Error> {
+    let masteries_url = format!(
+        "{}/lol/champion-mastery/v4/champion-masteries/by-puuid/{}/top?count={}",
+        platform.host(),
+        puuid,
+        count
+    );
+
+    let response = send_request(masteries_url.as_str(), Some(api_key), client).await?;
+    response
+        .error_for_status()?
+        .json::<Vec<ChampionMasteryDto>>()
+        .await
+}
+
+/// Returns `None` when the player has never played the champion
+pub async fn request_champion_mastery(
+    puuid: &str,
+    champion_id: i64,
+    platform: Platform,
+    api_key: &str,
+    client: &Client,
+) -> Result<Option<ChampionMasteryDto>, Error> {
+    let mastery_url = format!(
+        "{}/lol/champion-mastery/v4/champion-masteries/by-puuid/{}/by-champion/{}",
+        platform.host(),
+        puuid,
+        champion_id
+    );
+
+    let response = send_request(mastery_url.as_str(), Some(api_key), client).await?;
+    if response.status() == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+
+    let mastery = response
+        .error_for_status()?
+        .json::<ChampionMasteryDto>()
+        .await?;
+    Ok(Some(mastery))
 }
@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+
+use reqwest::{Client, Error};
+use serde::Deserialize;
+use tokio::sync::OnceCell;
+
+use super::requests::send_request;
+
+const DDRAGON_URL: &str = "https://ddragon.leagueoflegends.com";
+/// Data dragon has no Arena augments, Community Dragon does
+const ARENA_AUGMENTS_URL: &str = "https://raw.communitydragon.org/latest/cdragon/arena/en_us.json";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChampionInfo {
+    /// Data dragon id, e.g. `MonkeyKing`
+    pub id: String,
+    pub key: String,
+    /// Display name, e.g. `Wukong`
+    pub name: String,
+}
+
+impl ChampionInfo {
+    /// Name as used in u.gg urls
+    pub fn u_gg_name(&self) -> String {
+        match self.id.as_str() {
+            "MonkeyKing" => "wukong".to_string(),
+            id => id.to_lowercase(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ItemGold {
+    pub total: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ItemInfo {
+    pub name: String,
+    pub gold: ItemGold,
+    /// Items this one builds into, empty for finished items
+    #[serde(default)]
+    pub into: Vec<String>,
+}
+
+impl ItemInfo {
+    /// Finished items and boots, leaving out components, consumables and trinkets
+    pub fn is_completed(&self) -> bool {
+        self.into.is_empty() && self.gold.total >= 900
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SummonerSpellInfo {
+    key: String,
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AugmentInfo {
+    id: i64,
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ArenaResponse {
+    augments: Vec<AugmentInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DataDragonResponse<T> {
+    data: HashMap<String, T>,
+}
+
+static CHAMPIONS: OnceCell<HashMap<i64, ChampionInfo>> = OnceCell::const_new();
+static SUMMONER_SPELLS: OnceCell<HashMap<i64, String>> = OnceCell::const_new();
+static ITEMS: OnceCell<HashMap<i64, ItemInfo>> = OnceCell::const_new();
+static ARENA_AUGMENTS: OnceCell<HashMap<i64, String>> = OnceCell::const_new();
+
+async fn latest_version(client: &Client) -> Result<String, Error> {
+    let versions_url = format!("{}/api/versions.json", DDRAGON_URL);
+    let versions = send_request(&versions_url, None, client)
+        .await?
+        .json::<Vec<String>>()
+        .await?;
+
+    Ok(versions.into_iter().next().unwrap_or_default())
+}
+
+/// Champion data keyed by the numeric champion id Riot endpoints return
+pub async fn champions(client: &Client) -> Result<&'static HashMap<i64, ChampionInfo>, Error> {
+    CHAMPIONS
+        .get_or_try_init(|| async {
+            let version = latest_version(client).await?;
+            let champions_url = format!("{}/cdn/{}/data/en_US/champion.json", DDRAGON_URL, version);
+            let response = send_request(&champions_url, None, client)
+                .await?
+                .json::<DataDragonResponse<ChampionInfo>>()
+                .await?;
+
+            Ok(response
+                .data
+                .into_values()
+                .filter_map(|champion| Some((champion.key.parse().ok()?, champion)))
+                .collect())
+        })
+        .await
+}
+
+pub async fn summoner_spells(client: &Client) -> Result<&'static HashMap<i64, String>, Error> {
+    SUMMONER_SPELLS
+        .get_or_try_init(|| async {
+            let version = latest_version(client).await?;
+            let spells_url = format!("{}/cdn/{}/data/en_US/summoner.json", DDRAGON_URL, version);
+            let response = send_request(&spells_url, None, client)
+                .await?
+                .json::<DataDragonResponse<SummonerSpellInfo>>()
+                .await?;
+
+            Ok(response
+                .data
+                .into_values()
+                .filter_map(|spell| Some((spell.key.parse().ok()?, spell.name)))
+                .collect())
+        })
+        .await
+}
+
+/// Item data keyed by the item ids in match timelines
+pub async fn items(client: &Client) -> Result<&'static HashMap<i64, ItemInfo>, Error> {
+    ITEMS
+        .get_or_try_init(|| async {
+            let version = latest_version(client).await?;
+            let items_url = format!("{}/cdn/{}/data/en_US/item.json", DDRAGON_URL, version);
+            let response = send_request(&items_url, None, client)
+                .await?
+                .json::<DataDragonResponse<ItemInfo>>()
+                .await?;
+
+            Ok(response
+                .data
+                .into_iter()
+                .filter_map(|(id, item)| Some((id.parse().ok()?, item)))
+                .collect())
+        })
+        .await
+}
+
+/// Looks a champion up by display name or data dragon id, ignoring case, spaces and
+/// punctuation, so `kaisa`, `Kai'Sa` and `wukong` all match
+pub fn find_champion<'a>(
+    champions: &'a HashMap<i64, ChampionInfo>,
+    name: &str,
+) -> Option<&'a ChampionInfo> {
+    let normalize = |name: &str| {
+        name.chars()
+            .filter(|c| c.is_alphanumeric())
+            .collect::<String>()
+            .to_lowercase()
+    };
+    let name = normalize(name);
+    champions
+        .values()
+        .find(|champion| normalize(&champion.name) == name || normalize(&champion.id) == name)
+}
+
+/// Arena augment names keyed by the `playerAugment` ids in match data
+pub async fn arena_augments(client: &Client) -> Result<&'static HashMap<i64, String>, Error> {
+    ARENA_AUGMENTS
+        .get_or_try_init(|| async {
+            let response = send_request(ARENA_AUGMENTS_URL, None, client)
+                .await?
+                .json::<ArenaResponse>()
+                .await?;
+
+            Ok(response
+                .augments
+                .into_iter()
+                .map(|augment| (augment.id, augment.name))
+                .collect())
+        })
+        .await
+}
+
+pub fn queue_name(queue_id: i64) -> &'static str {
+    match queue_id {
+        0 => "Custom",
+        400 => "Normal Draft",
+        420 => "Ranked Solo/Duo",
+        430 => "Normal Blind",
+        440 => "Ranked Flex",
+        450 => "ARAM",
+        490 => "Quickplay",
+        700 | 720 => "Clash",
+        830 | 840 | 850 | 870 | 880 | 890 => "Co-op vs. AI",
+        900 | 1010 | 1900 => "URF",
+        1700 | 1710 => "Arena",
+        _ => "Other",
+    }
+}
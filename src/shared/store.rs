@@ -0,0 +1,420 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use chrono::Utc;
+use serde::{Deserialize, Deserializer, Serialize};
+use tokio::sync::{RwLock, RwLockReadGuard};
+use tracing::{error, info};
+
+use super::types::{LeagueEntryDto, MatchDto, Platform, TimelineDto};
+
+const STORE_PATH: &str = "data/store.json";
+
+/// Keeps temporary file names unique when the same file is saved twice at once
+static TEMP_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Written to a temporary file and renamed over `path`, so a crash or full disk mid-write
+/// leaves the previous version (or nothing) instead of a truncated file
+async fn write_atomic(path: &Path, contents: String) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let temp_path = path.with_extension(format!(
+        "tmp-{}",
+        TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    tokio::fs::write(&temp_path, contents).await?;
+    tokio::fs::rename(&temp_path, path).await
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkedAccount {
+    pub puuid: String,
+    pub player_name: String,
+    pub tag: String,
+    pub platform: Platform,
+}
+
+impl LinkedAccount {
+    pub fn riot_id(&self) -> String {
+        format!("{}#{}", self.player_name, self.tag)
+    }
+}
+
+/// Stores from before alt accounts hold a single account per user
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredAccounts {
+    Many(Vec<LinkedAccount>),
+    One(LinkedAccount),
+}
+
+fn deserialize_linked_accounts<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<HashMap<u64, Vec<LinkedAccount>>, D::Error> {
+    let stored = HashMap::<u64, StoredAccounts>::deserialize(deserializer)?;
+    Ok(stored
+        .into_iter()
+        .map(|(user_id, accounts)| match accounts {
+            StoredAccounts::Many(accounts) => (user_id, accounts),
+            StoredAccounts::One(account) => (user_id, vec![account]),
+        })
+        .collect())
+}
+
+/// A saved player command, replayed through `/run`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Shortcut {
+    /// One of the player commands, e.g. `matches`
+    pub command: String,
+    pub player_name: String,
+    pub tag: String,
+    pub platform: Platform,
+    pub game_count: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RankAlertSettings {
+    pub enabled: bool,
+    /// LP gained or lost since the last alert that is worth posting about
+    pub lp_swing: i32,
+    /// Post tier and division promotions/demotions
+    pub divisions: bool,
+}
+
+impl Default for RankAlertSettings {
+    fn default() -> Self {
+        RankAlertSettings {
+            enabled: false,
+            lp_swing: 75,
+            divisions: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TiltAlertSettings {
+    pub enabled: bool,
+    /// Ranked losses in a row before suggesting a break
+    pub losses: usize,
+    /// Every loss of the streak has to have ended within this many hours
+    pub window_hours: i64,
+}
+
+impl Default for TiltAlertSettings {
+    fn default() -> Self {
+        TiltAlertSettings {
+            enabled: false,
+            losses: 3,
+            window_hours: 3,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct GuildSettings {
+    /// Channel post-game summaries are posted in, `None` turns them off
+    pub announcement_channel: Option<u64>,
+    /// Discord users who ran /link in this guild
+    #[serde(default)]
+    pub members: HashSet<u64>,
+    #[serde(default)]
+    pub rank_alerts: RankAlertSettings,
+    /// PUUID -> solo queue entry at the last rank alert, swings are measured from here
+    #[serde(default)]
+    pub rank_baselines: HashMap<String, LeagueEntryDto>,
+    /// Channel the Monday recap is posted in, `None` turns it off
+    #[serde(default)]
+    pub recap_channel: Option<u64>,
+    /// ISO week (e.g. `2024-W07`) of the last recap, so restarts don't post twice
+    #[serde(default)]
+    pub last_recap_week: Option<String>,
+    /// Post in the announcement channel when a linked player starts a game
+    #[serde(default)]
+    pub live_games: bool,
+    /// Game id -> status message posted for it, edited once the game is over
+    #[serde(default)]
+    pub live_messages: HashMap<i64, u64>,
+    #[serde(default)]
+    pub tilt_alerts: TiltAlertSettings,
+    /// PUUID -> newest loss at the last tilt alert, so the same streak isn't posted again
+    #[serde(default)]
+    pub tilt_alerted: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RankSnapshot {
+    /// Unix seconds
+    pub timestamp: i64,
+    pub entry: LeagueEntryDto,
+}
+
+/// Progress of loading a player's match history for the season
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeasonBackfill {
+    pub account: LinkedAccount,
+    /// Year the season started in
+    pub season: i32,
+    /// Unix seconds the last complete walk of the season started at, `None` until the first
+    /// one finishes. Later walks only page from here
+    #[serde(default)]
+    pub synced_until: Option<i64>,
+    /// Unix seconds of the last /season lookup, unlinked players stop being polled a while
+    /// after it
+    #[serde(default)]
+    pub looked_up: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexedMatch {
+    pub match_id: String,
+    /// Unix milliseconds, matches the Riot field
+    pub game_end_timestamp: i64,
+}
+
+/// Everything the bot persists between restarts
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct StoreData {
+    /// Discord user id -> Riot accounts, the primary one first
+    #[serde(default, deserialize_with = "deserialize_linked_accounts")]
+    pub linked_accounts: HashMap<u64, Vec<LinkedAccount>>,
+    /// Guild id -> shortcut name -> shortcut
+    #[serde(default)]
+    pub shortcuts: HashMap<u64, HashMap<String, Shortcut>>,
+    #[serde(default)]
+    pub guild_settings: HashMap<u64, GuildSettings>,
+    /// PUUID -> newest match id that has already been announced
+    #[serde(default)]
+    pub last_seen_match: HashMap<String, String>,
+    /// PUUID -> last known solo queue entry, used for LP changes
+    #[serde(default)]
+    pub solo_rank: HashMap<String, LeagueEntryDto>,
+    /// PUUID -> solo queue snapshots, oldest first
+    #[serde(default)]
+    pub rank_history: HashMap<String, Vec<RankSnapshot>>,
+    /// PUUID -> cached matches the player took part in, newest first
+    #[serde(default)]
+    pub match_index: HashMap<String, Vec<IndexedMatch>>,
+    /// PUUID -> season history loading, for linked players and anyone looked up with /season
+    #[serde(default)]
+    pub season_backfills: HashMap<String, SeasonBackfill>,
+}
+
+impl StoreData {
+    /// Appends a snapshot when the rank changed, or the last one is a day old so flat
+    /// stretches still show on charts
+    pub fn record_rank_snapshot(&mut self, puuid: &str, timestamp: i64, entry: &LeagueEntryDto) {
+        let history = self.rank_history.entry(puuid.to_string()).or_default();
+        let unchanged = history.last().is_some_and(|last| {
+            last.entry.absolute_league_points() == entry.absolute_league_points()
+                && timestamp - last.timestamp < 24 * 60 * 60
+        });
+        if !unchanged {
+            history.push(RankSnapshot {
+                timestamp,
+                entry: entry.clone(),
+            });
+        }
+    }
+
+    /// Latest stored solo queue entry, from the rank history or the last announcement
+    pub fn latest_solo_rank(&self, puuid: &str) -> Option<&LeagueEntryDto> {
+        self.rank_history
+            .get(puuid)
+            .and_then(|history| history.last())
+            .map(|snapshot| &snapshot.entry)
+            .or_else(|| self.solo_rank.get(puuid))
+    }
+
+    /// Account commands default to when no `account` is picked
+    pub fn primary_account(&self, user_id: u64) -> Option<&LinkedAccount> {
+        self.linked_accounts.get(&user_id)?.first()
+    }
+
+    /// Every account of the guild's members, skipping members who have since unlinked
+    pub fn guild_members(&self, guild_id: u64) -> Vec<(u64, &[LinkedAccount])> {
+        let Some(settings) = self.guild_settings.get(&guild_id) else {
+            return vec![];
+        };
+        let mut members: Vec<(u64, &[LinkedAccount])> = settings
+            .members
+            .iter()
+            .filter_map(|user_id| Some((*user_id, self.linked_accounts.get(user_id)?.as_slice())))
+            .filter(|(_, accounts)| !accounts.is_empty())
+            .collect();
+        members.sort_by_key(|(user_id, _)| *user_id);
+        members
+    }
+
+    /// `guild_members` flattened to one entry per account, alts included
+    pub fn guild_accounts(&self, guild_id: u64) -> Vec<(u64, &LinkedAccount)> {
+        self.guild_members(guild_id)
+            .into_iter()
+            .flat_map(|(user_id, accounts)| accounts.iter().map(move |account| (user_id, account)))
+            .collect()
+    }
+}
+
+/// JSON file backed store, the whole file is rewritten on every update. Full `MatchDto`s are
+/// too big for that and live one file each in a `matches` directory next to it
+pub struct Store {
+    path: PathBuf,
+    data: RwLock<StoreData>,
+}
+
+impl Store {
+    pub fn load() -> Self {
+        let path = PathBuf::from(STORE_PATH);
+        let data = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|err| {
+                // Starting empty would overwrite every link and setting on the next update,
+                // so the unreadable file is kept next to the new one
+                let backup =
+                    path.with_extension(format!("json.corrupt-{}", Utc::now().timestamp()));
+                if let Err(rename_err) = fs::rename(&path, &backup) {
+                    panic!(
+                        "Could not parse {:?} ({}) or move it aside: {}",
+                        path, err, rename_err
+                    );
+                }
+                error!(
+                    "Could not parse {:?}, moved it to {:?} and starting empty: {}",
+                    path, backup, err
+                );
+                StoreData::default()
+            }),
+            Err(_) => {
+                info!("No store found at {:?}, starting empty", path);
+                StoreData::default()
+            }
+        };
+
+        Store {
+            path,
+            data: RwLock::new(data),
+        }
+    }
+
+    pub async fn read(&self) -> RwLockReadGuard<'_, StoreData> {
+        self.data.read().await
+    }
+
+    pub async fn update<R>(&self, f: impl FnOnce(&mut StoreData) -> R) -> std::io::Result<R> {
+        let mut data = self.data.write().await;
+        let result = f(&mut data);
+
+        // The lock is held until the write is done to keep writes in order
+        write_atomic(&self.path, serde_json::to_string(&*data)?).await?;
+
+        Ok(result)
+    }
+
+    fn match_path(&self, match_id: &str) -> PathBuf {
+        self.path
+            .with_file_name("matches")
+            .join(format!("{}.json", match_id))
+    }
+
+    pub fn load_match(&self, match_id: &str) -> Option<MatchDto> {
+        let contents = fs::read_to_string(self.match_path(match_id)).ok()?;
+        serde_json::from_str(&contents).ok()
+    }
+
+    fn timeline_path(&self, match_id: &str) -> PathBuf {
+        self.path
+            .with_file_name("timelines")
+            .join(format!("{}.json", match_id))
+    }
+
+    pub fn load_timeline(&self, match_id: &str) -> Option<TimelineDto> {
+        let contents = fs::read_to_string(self.timeline_path(match_id)).ok()?;
+        serde_json::from_str(&contents).ok()
+    }
+
+    /// Timelines don't change once a game is over, so like matches they are fetched once
+    pub async fn save_timeline(
+        &self,
+        match_id: &str,
+        timeline: &TimelineDto,
+    ) -> std::io::Result<()> {
+        write_atomic(
+            &self.timeline_path(match_id),
+            serde_json::to_string(timeline)?,
+        )
+        .await
+    }
+
+    /// Caches the matches and indexes them under the player they were fetched for. Files
+    /// that don't parse are written again
+    pub async fn save_matches(&self, puuid: &str, matches: &[MatchDto]) -> std::io::Result<()> {
+        for match_dto in matches {
+            let match_id = &match_dto.metadata.match_id;
+            if self.load_match(match_id).is_none() {
+                write_atomic(
+                    &self.match_path(match_id),
+                    serde_json::to_string(match_dto)?,
+                )
+                .await?;
+            }
+        }
+
+        // Skips rewriting the store when everything is indexed already
+        let all_indexed = self
+            .read()
+            .await
+            .match_index
+            .get(puuid)
+            .is_some_and(|indexed| {
+                matches.iter().all(|match_dto| {
+                    indexed
+                        .iter()
+                        .any(|m| m.match_id == match_dto.metadata.match_id)
+                })
+            });
+        if all_indexed {
+            return Ok(());
+        }
+
+        // Checked again under the write lock, another save for the player may have indexed
+        // some of them since
+        self.update(|data| {
+            let indexed = data.match_index.entry(puuid.to_string()).or_default();
+            let mut seen: HashSet<String> = indexed.iter().map(|m| m.match_id.clone()).collect();
+            for match_dto in matches {
+                if seen.insert(match_dto.metadata.match_id.clone()) {
+                    indexed.push(IndexedMatch {
+                        match_id: match_dto.metadata.match_id.clone(),
+                        game_end_timestamp: match_dto.info.game_end_timestamp,
+                    });
+                }
+            }
+            indexed.sort_by_key(|m| std::cmp::Reverse(m.game_end_timestamp));
+            // Drops duplicates indexed before saves were checked under the lock
+            indexed.dedup_by(|a, b| a.match_id == b.match_id);
+        })
+        .await
+    }
+
+    /// Cached matches of the player that ended after `since` (unix milliseconds), newest first
+    pub async fn cached_matches(&self, puuid: &str, since: i64) -> Vec<MatchDto> {
+        let match_ids: Vec<String> = self
+            .read()
+            .await
+            .match_index
+            .get(puuid)
+            .into_iter()
+            .flatten()
+            .take_while(|m| m.game_end_timestamp >= since)
+            .map(|m| m.match_id.clone())
+            .collect();
+
+        match_ids
+            .iter()
+            .filter_map(|match_id| self.load_match(match_id))
+            .collect()
+    }
+}
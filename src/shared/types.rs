@@ -1,12 +1,136 @@
 use serde::{Deserialize, Serialize};
-use serenity::all::Colour;
-use std::fmt::Display;
+use serenity::all::{Colour, CreateActionRow, CreateAttachment, CreateButton, CreateEmbed};
+use std::{collections::HashMap, fmt::Display};
+use strum::{AsRefStr, Display as StrumDisplay, EnumIter, EnumString};
 
 #[derive(Deserialize, Debug)]
 pub struct AccountDto {
     pub puuid: String,
 }
 
+/// Regional routing values, used by account-v1 and match-v5
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    AsRefStr,
+    StrumDisplay,
+    EnumString,
+    EnumIter,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase", ascii_case_insensitive)]
+pub enum Region {
+    Americas,
+    Asia,
+    Europe,
+    Sea,
+}
+
+impl Region {
+    pub fn host(&self) -> String {
+        format!("https://{}.api.riotgames.com", self.as_ref())
+    }
+}
+
+/// Platform routing values (the server a player picks), used by league-v4,
+/// summoner-v4, spectator-v5 and champion-mastery-v4
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    AsRefStr,
+    StrumDisplay,
+    EnumString,
+    EnumIter,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase", ascii_case_insensitive)]
+pub enum Platform {
+    #[default]
+    Na1,
+    Br1,
+    La1,
+    La2,
+    Euw1,
+    Eun1,
+    Tr1,
+    Ru,
+    Me1,
+    Kr,
+    Jp1,
+    Oc1,
+    Ph2,
+    Sg2,
+    Th2,
+    Tw2,
+    Vn2,
+}
+
+impl Platform {
+    pub fn host(&self) -> String {
+        format!("https://{}.api.riotgames.com", self.as_ref())
+    }
+
+    /// Region that match-v5 data for this platform lives in
+    pub fn region(&self) -> Region {
+        match self {
+            Platform::Na1 | Platform::Br1 | Platform::La1 | Platform::La2 => Region::Americas,
+            Platform::Euw1 | Platform::Eun1 | Platform::Tr1 | Platform::Ru | Platform::Me1 => {
+                Region::Europe
+            }
+            Platform::Kr | Platform::Jp1 => Region::Asia,
+            Platform::Oc1
+            | Platform::Ph2
+            | Platform::Sg2
+            | Platform::Th2
+            | Platform::Tw2
+            | Platform::Vn2 => Region::Sea,
+        }
+    }
+
+    /// account-v1 has no SEA cluster, any of the other regions can look up SEA players
+    pub fn account_region(&self) -> Region {
+        match self.region() {
+            Region::Sea => Region::Asia,
+            region => region,
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Platform::Na1 => "North America",
+            Platform::Br1 => "Brazil",
+            Platform::La1 => "Latin America North",
+            Platform::La2 => "Latin America South",
+            Platform::Euw1 => "Europe West",
+            Platform::Eun1 => "Europe Nordic & East",
+            Platform::Tr1 => "Turkey",
+            Platform::Ru => "Russia",
+            Platform::Me1 => "Middle East",
+            Platform::Kr => "Korea",
+            Platform::Jp1 => "Japan",
+            Platform::Oc1 => "Oceania",
+            Platform::Ph2 => "Philippines",
+            Platform::Sg2 => "Singapore",
+            Platform::Th2 => "Thailand",
+            Platform::Tw2 => "Taiwan",
+            Platform::Vn2 => "Vietnam",
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct OutputError {
     pub status: String,
@@ -32,7 +156,7 @@ pub struct AccountInfoContext {
     pub puuid: String,
     pub player_name: String,
     pub tag: String,
-    pub region: String,
+    pub platform: Platform,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -45,7 +169,7 @@ pub struct MatchDto {
 #[serde(rename_all = "camelCase")]
 pub struct MetadataDto {
     data_version: String,
-    match_id: String,
+    pub match_id: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -53,11 +177,23 @@ pub struct MetadataDto {
 pub struct InfoDto {
     pub participants: Vec<ParticipantDto>,
     pub game_mode: String,
+    /// Seconds
+    pub game_duration: i64,
+    pub queue_id: i64,
+    /// Unix milliseconds
+    #[serde(default)]
+    pub game_start_timestamp: i64,
+    /// Unix milliseconds, missing on matches from before patch 11.20
+    #[serde(default)]
+    pub game_end_timestamp: i64,
 }
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ParticipantDto {
     pub assists: i32,
+    /// 0 on matches cached before the id was stored
+    #[serde(default)]
+    pub champion_id: i64,
     pub champion_name: String,
     pub deaths: i32,
     pub kills: i32,
@@ -68,11 +204,255 @@ pub struct ParticipantDto {
     pub team_position: String,
     pub win: bool,
     pub riot_id_game_name: String,
+    #[serde(default)]
+    pub riot_id_tagline: String,
     pub team_id: u32,
+    pub total_minions_killed: i32,
+    pub neutral_minions_killed: i32,
+    pub total_damage_dealt_to_champions: i32,
+    #[serde(default)]
+    pub gold_earned: i32,
+    #[serde(default)]
+    pub vision_score: i32,
+    #[serde(default)]
+    pub damage_dealt_to_objectives: i32,
+    /// Arena finishing place, 0 in other modes
+    #[serde(default)]
+    pub placement: i32,
+    /// Arena duo the player was in, 0 in other modes
+    #[serde(default)]
+    pub player_subteam_id: i32,
+    #[serde(default)]
+    pub player_augment1: i64,
+    #[serde(default)]
+    pub player_augment2: i64,
+    #[serde(default)]
+    pub player_augment3: i64,
+    #[serde(default)]
+    pub player_augment4: i64,
+    #[serde(default)]
+    pub player_augment5: i64,
+    #[serde(default)]
+    pub player_augment6: i64,
+    #[serde(default)]
+    pub game_ended_in_early_surrender: bool,
+}
+
+impl ParticipantDto {
+    /// Arena augment ids in pick order, empty slots skipped
+    pub fn augments(&self) -> Vec<i64> {
+        [
+            self.player_augment1,
+            self.player_augment2,
+            self.player_augment3,
+            self.player_augment4,
+            self.player_augment5,
+            self.player_augment6,
+        ]
+        .into_iter()
+        .filter(|augment| *augment != 0)
+        .collect()
+    }
+
+    /// Early surrenders only happen in remakes, nobody wins or loses those
+    pub fn is_remake(&self) -> bool {
+        self.game_ended_in_early_surrender
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TimelineDto {
+    pub info: TimelineInfoDto,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineInfoDto {
+    /// Milliseconds between frames, usually a minute
+    pub frame_interval: i64,
+    pub frames: Vec<FrameDto>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FrameDto {
+    pub events: Vec<EventDto>,
+    /// Participant id (`"1"` to `"10"`) -> stats at this frame
+    pub participant_frames: HashMap<String, ParticipantFrameDto>,
+    /// Milliseconds since the game started
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParticipantFrameDto {
+    pub participant_id: i32,
+    pub total_gold: i32,
+    pub xp: i32,
+    pub level: i32,
+}
+
+/// Map coordinates, (0, 0) is the blue side corner of Summoner's Rift
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PositionDto {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// Only the fields of the event types the bot reads, the rest vary per `event_type`
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventDto {
+    #[serde(rename = "type")]
+    pub event_type: String,
+    pub timestamp: i64,
+    pub killer_id: Option<i32>,
+    pub victim_id: Option<i32>,
+    pub killer_team_id: Option<u32>,
+    /// For buildings this is the team that lost it
+    pub team_id: Option<u32>,
+    pub building_type: Option<String>,
+    pub monster_type: Option<String>,
+    pub monster_sub_type: Option<String>,
+    /// Acting player of item and skill events
+    pub participant_id: Option<i32>,
+    pub item_id: Option<i64>,
+    /// Item taken back by an `ITEM_UNDO`
+    pub before_id: Option<i64>,
+    /// 1-4 for Q, W, E and R
+    pub skill_slot: Option<usize>,
+    /// `NORMAL` for skill points, `EVOLVE` for Kha'Zix style evolutions
+    pub level_up_type: Option<String>,
+    /// Where a `CHAMPION_KILL` happened
+    pub position: Option<PositionDto>,
 }
 
 impl std::error::Error for OutputError {}
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LeagueEntryDto {
+    pub queue_type: String,
+    pub tier: String,
+    pub rank: String,
+    pub league_points: i32,
+    pub wins: i32,
+    pub losses: i32,
+}
+
+impl LeagueEntryDto {
+    pub fn rank_string(&self) -> String {
+        match self.tier.as_str() {
+            "MASTER" | "GRANDMASTER" | "CHALLENGER" => {
+                format!("{} {}LP", self.tier, self.league_points)
+            }
+            _ => format!("{} {} {}LP", self.tier, self.rank, self.league_points),
+        }
+    }
+
+    /// LP counted from the bottom of Iron IV, so entries in different tiers can be compared
+    pub fn absolute_league_points(&self) -> i32 {
+        let tier = match self.tier.as_str() {
+            "IRON" => 0,
+            "BRONZE" => 1,
+            "SILVER" => 2,
+            "GOLD" => 3,
+            "PLATINUM" => 4,
+            "EMERALD" => 5,
+            "DIAMOND" => 6,
+            // Apex tiers share one ladder
+            _ => 7,
+        };
+        let division = match self.rank.as_str() {
+            "IV" => 0,
+            "III" => 1,
+            "II" => 2,
+            "I" => 3,
+            _ => 0,
+        };
+        if tier == 7 {
+            return tier * 400 + self.league_points;
+        }
+        tier * 400 + division * 100 + self.league_points
+    }
+}
+
+/// Inverse of `LeagueEntryDto::absolute_league_points`, for chart axes
+pub fn tier_label(absolute_league_points: i32) -> String {
+    const TIERS: [&str; 7] = [
+        "Iron", "Bronze", "Silver", "Gold", "Plat", "Emerald", "Diamond",
+    ];
+    const DIVISIONS: [&str; 4] = ["IV", "III", "II", "I"];
+
+    let tier = (absolute_league_points / 400).max(0) as usize;
+    match TIERS.get(tier) {
+        Some(tier_name) => {
+            let division = ((absolute_league_points % 400) / 100) as usize;
+            format!("{} {}", tier_name, DIVISIONS[division.min(3)])
+        }
+        None => format!("Master+ {}", absolute_league_points - 7 * 400),
+    }
+}
+
+pub const SOLO_QUEUE: &str = "RANKED_SOLO_5x5";
+
+pub fn solo_queue_entry(entries: &[LeagueEntryDto]) -> Option<&LeagueEntryDto> {
+    entries.iter().find(|entry| entry.queue_type == SOLO_QUEUE)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChampionMasteryDto {
+    pub champion_id: i64,
+    pub champion_level: i32,
+    pub champion_points: i64,
+    /// Unix milliseconds
+    pub last_play_time: i64,
+    #[serde(default)]
+    pub champion_points_until_next_level: i64,
+    /// Milestones reached this season
+    #[serde(default)]
+    pub champion_season_milestone: i32,
+    /// Grades counting towards the next milestone, e.g. `["S-", "A"]`
+    #[serde(default)]
+    pub milestone_grades: Vec<String>,
+    #[serde(default)]
+    pub next_season_milestone: Option<NextSeasonMilestoneDto>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NextSeasonMilestoneDto {
+    /// Grade -> games with at least that grade needed, e.g. `"B-": 3`
+    #[serde(default)]
+    pub require_grade_counts: HashMap<String, i32>,
+    #[serde(default)]
+    pub reward_marks: i32,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurrentGameInfo {
+    pub game_id: i64,
+    pub game_mode: String,
+    pub game_queue_config_id: Option<i64>,
+    pub game_start_time: i64,
+    pub game_length: i64,
+    pub participants: Vec<CurrentGameParticipant>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurrentGameParticipant {
+    pub champion_id: i64,
+    pub puuid: Option<String>,
+    pub team_id: u32,
+    pub spell1_id: i64,
+    pub spell2_id: i64,
+    #[serde(default)]
+    pub riot_id: String,
+}
+
 #[derive(Debug)]
 pub struct DiscordOutput {
     pub color: Colour,
@@ -81,6 +461,10 @@ pub struct DiscordOutput {
     pub footer: String,
     pub title: String,
     pub content: String,
+    /// (custom_id, label) pairs rendered as a row of buttons under the embed
+    pub buttons: Vec<(String, String)>,
+    /// (file name, bytes) uploaded with the message, the first PNG is shown as the embed image
+    pub attachments: Vec<(String, Vec<u8>)>,
 }
 
 impl DiscordOutput {
@@ -99,6 +483,57 @@ impl DiscordOutput {
             footer,
             title,
             content,
+            buttons: vec![],
+            attachments: vec![],
         }
     }
+
+    pub fn to_embed(&self) -> CreateEmbed {
+        let embed = CreateEmbed::new()
+            .title(&self.title)
+            .description(&self.description)
+            .color(self.color)
+            .fields(self.fields.clone());
+
+        match self
+            .attachments
+            .iter()
+            .find(|(file_name, _)| file_name.ends_with(".png"))
+        {
+            Some((file_name, _)) => embed.image(format!("attachment://{}", file_name)),
+            None => embed,
+        }
+    }
+
+    pub fn to_attachments(&self) -> Vec<CreateAttachment> {
+        self.attachments
+            .iter()
+            .map(|(file_name, data)| CreateAttachment::bytes(data.clone(), file_name.clone()))
+            .collect()
+    }
+
+    pub fn to_components(&self) -> Vec<CreateActionRow> {
+        // Discord allows 5 buttons per row
+        self.buttons
+            .chunks(5)
+            .map(|buttons| {
+                CreateActionRow::Buttons(
+                    buttons
+                        .iter()
+                        .map(|(custom_id, label)| CreateButton::new(custom_id).label(label))
+                        .collect(),
+                )
+            })
+            .collect()
+    }
+
+    pub fn with_buttons(mut self, buttons: Vec<(String, String)>) -> Self {
+        self.buttons = buttons;
+        self
+    }
+
+    pub fn with_attachment(mut self, file_name: String, data: Vec<u8>) -> Self {
+        self.attachments.push((file_name, data));
+        self
+    }
 }
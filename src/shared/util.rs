@@ -1,5 +1,8 @@
-use std::time::Duration;
-use tokio::time::sleep;
+use std::{collections::VecDeque, time::Duration};
+use tokio::{
+    sync::Mutex,
+    time::{sleep, sleep_until, Instant},
+};
 
 pub async fn retry_requests(
     request_to_make: reqwest::Request,
@@ -12,11 +15,15 @@ pub async fn retry_requests(
     let mut result = client.execute(request_to_make.try_clone().unwrap()).await;
 
     result = loop {
-        let status = result.as_ref().unwrap().status();
-
-        if result.is_ok() && status.is_success() {
-            println!("Request to {} successful", request_to_make.url());
-            break result;
+        // Connection errors are retried like error statuses
+        match &result {
+            Ok(response) if response.status().is_success() => {
+                println!("Request to {} successful", request_to_make.url());
+                break result;
+            }
+            // Riot uses 404 for "no such player/game", retrying will not change the answer
+            Ok(response) if response.status() == reqwest::StatusCode::NOT_FOUND => break result,
+            _ => {}
         }
         if retries >= max_retries {
             println!(
@@ -38,3 +45,68 @@ pub async fn retry_requests(
     };
     result
 }
+
+/// Share of each limit background pollers may use, the rest is kept free for commands
+const BACKGROUND_SHARE: f64 = 0.75;
+
+/// Sliding window limiter shared by pollers and commands, so together they stay under the
+/// Riot dev key's limits (20 requests a second and 100 every 2 minutes)
+pub struct RateLimiter {
+    /// (requests, window) pairs that all have to hold
+    limits: Vec<(usize, Duration)>,
+    sent: Mutex<VecDeque<Instant>>,
+}
+
+impl RateLimiter {
+    pub fn new(limits: Vec<(usize, Duration)>) -> Self {
+        RateLimiter {
+            limits,
+            sent: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Waits until another request fits in every window and reserves it
+    pub async fn acquire(&self) {
+        self.acquire_share(1.0).await
+    }
+
+    /// Like `acquire`, but only fills part of each window so commands still get through
+    pub async fn acquire_background(&self) {
+        self.acquire_share(BACKGROUND_SHARE).await
+    }
+
+    async fn acquire_share(&self, share: f64) {
+        loop {
+            // The lock isn't held while waiting, a command can take a slot a poller is
+            // waiting on
+            let ready_at = {
+                let mut sent = self.sent.lock().await;
+                let longest = self.limits.iter().map(|(_, window)| *window).max();
+                while sent.front().is_some_and(|oldest| {
+                    longest.is_some_and(|longest| oldest.elapsed() >= longest)
+                }) {
+                    sent.pop_front();
+                }
+
+                let ready_at = self
+                    .limits
+                    .iter()
+                    .filter_map(|(max_requests, window)| {
+                        let max_requests = ((*max_requests as f64 * share) as usize).max(1);
+                        // Once this request leaves the window there is room for another
+                        let blocking = *sent.get(sent.len().checked_sub(max_requests)?)?;
+                        (blocking.elapsed() < *window).then_some(blocking + *window)
+                    })
+                    .max();
+                match ready_at {
+                    Some(ready_at) => ready_at,
+                    None => {
+                        sent.push_back(Instant::now());
+                        return;
+                    }
+                }
+            };
+            sleep_until(ready_at).await;
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use std::str::FromStr;
+
+use reqwest::Client;
+use serenity::all::{Colour, CommandDataOption, CommandDataOptionValue, GuildId};
+
+use crate::{
+    live, matches,
+    shared::{
+        store::{Shortcut, Store},
+        types::{DiscordOutput, Platform},
+        util::RateLimiter,
+    },
+    stats,
+};
+
+/// Player commands a shortcut can replay
+pub const SHORTCUT_COMMANDS: [&str; 3] = ["matches", "live", "stats"];
+
+const MAX_AUTOCOMPLETE_CHOICES: usize = 25;
+
+pub async fn handle_shortcut_command(
+    guild_id: GuildId,
+    subcommand: &CommandDataOption,
+    store: &Store,
+) -> Result<DiscordOutput, Box<dyn std::error::Error>> {
+    let options = match &subcommand.value {
+        CommandDataOptionValue::SubCommand(options) => options,
+        _ => return Err("Expected a subcommand".into()),
+    };
+    let iter = options.iter();
+    let name = iter
+        .clone()
+        .find(|opt| opt.name == "name")
+        .and_then(|opt| opt.value.as_str())
+        .map(|name| name.trim().to_lowercase());
+
+    match (subcommand.name.as_str(), name) {
+        ("add", Some(name)) => {
+            let command = iter
+                .clone()
+                .find(|opt| opt.name == "command")
+                .and_then(|opt| opt.value.as_str())
+                .unwrap();
+            let player_name = iter
+                .clone()
+                .find(|opt| opt.name == "player")
+                .and_then(|opt| opt.value.as_str())
+                .unwrap();
+            let tag = iter
+                .clone()
+                .find(|opt| opt.name == "tag")
+                .and_then(|opt| opt.value.as_str())
+                .unwrap();
+            let platform = iter
+                .clone()
+                .find(|opt| opt.name == "region")
+                .and_then(|opt| opt.value.as_str())
+                .and_then(|region| Platform::from_str(region).ok())
+                .unwrap_or_default();
+            let game_count = iter
+                .clone()
+                .find(|opt| opt.name == "game_count")
+                .and_then(|opt| opt.value.as_i64());
+
+            let shortcut = Shortcut {
+                command: command.to_string(),
+                player_name: player_name.to_string(),
+                tag: tag.to_string(),
+                platform,
+                game_count,
+            };
+            let description = format!("`/run {}` -> {}", name, describe_shortcut(&shortcut));
+            store
+                .update(|data| {
+                    data.shortcuts
+                        .entry(guild_id.get())
+                        .or_default()
+                        .insert(name, shortcut)
+                })
+                .await?;
+
+            Ok(DiscordOutput::new(
+                Colour::DARK_GREEN,
+                description,
+                vec![],
+                "".to_string(),
+                "Shortcut saved".to_string(),
+                "".to_string(),
+            ))
+        }
+        ("remove", Some(name)) => {
+            let removed = store
+                .update(|data| {
+                    data.shortcuts
+                        .get_mut(&guild_id.get())
+                        .and_then(|shortcuts| shortcuts.remove(&name))
+                })
+                .await?;
+
+            match removed {
+                Some(_) => Ok(DiscordOutput::new(
+                    Colour::DARK_GREEN,
+                    format!("Removed `{}`", name),
+                    vec![],
+                    "".to_string(),
+                    "Shortcut removed".to_string(),
+                    "".to_string(),
+                )),
+                None => Err(format!("No shortcut named `{}`", name).into()),
+            }
+        }
+        ("list", _) => {
+            let data = store.read().await;
+            let mut lines: Vec<String> = data
+                .shortcuts
+                .get(&guild_id.get())
+                .into_iter()
+                .flatten()
+                .map(|(name, shortcut)| format!("`{}` - {}", name, describe_shortcut(shortcut)))
+                .collect();
+            lines.sort();
+
+            Ok(DiscordOutput::new(
+                Colour::DARK_GREEN,
+                if lines.is_empty() {
+                    "No shortcuts yet, add one with `/shortcut add`".to_string()
+                } else {
+                    lines.join("\n")
+                },
+                vec![],
+                "".to_string(),
+                "Shortcuts".to_string(),
+                "".to_string(),
+            ))
+        }
+        (subcommand, _) => Err(format!("Unknown shortcut subcommand {}", subcommand).into()),
+    }
+}
+
+pub async fn handle_run_command(
+    guild_id: GuildId,
+    name: &str,
+    api_key: &str,
+    client: &Client,
+    store: &Store,
+    limiter: &RateLimiter,
+) -> Result<DiscordOutput, Box<dyn std::error::Error>> {
+    let name = name.trim().to_lowercase();
+    let shortcut = store
+        .read()
+        .await
+        .shortcuts
+        .get(&guild_id.get())
+        .and_then(|shortcuts| shortcuts.get(&name))
+        .cloned()
+        .ok_or_else(|| format!("No shortcut named `{}`", name))?;
+
+    let Shortcut {
+        command,
+        player_name,
+        tag,
+        platform,
+        game_count,
+    } = shortcut;
+
+    match command.as_str() {
+        "matches" => {
+            matches::handle_matches_command(
+                &player_name,
+                &tag,
+                platform,
+                game_count.unwrap_or(20),
+                api_key,
+                client,
+                store,
+                limiter,
+            )
+            .await
+        }
+        "live" => {
+            live::handle_live_command(&player_name, &tag, platform, api_key, client, limiter).await
+        }
+        "stats" => {
+            stats::handle_stats_command(
+                &player_name,
+                &tag,
+                platform,
+                game_count.unwrap_or(20),
+                api_key,
+                client,
+                store,
+                limiter,
+            )
+            .await
+        }
+        command => Err(format!("Shortcut `{}` runs unknown command {}", name, command).into()),
+    }
+}
+
+/// Shortcut names in the guild starting with what has been typed so far
+pub async fn autocomplete_shortcut_names(
+    guild_id: GuildId,
+    typed: &str,
+    store: &Store,
+) -> Vec<String> {
+    let typed = typed.trim().to_lowercase();
+    let data = store.read().await;
+    let mut names: Vec<String> = data
+        .shortcuts
+        .get(&guild_id.get())
+        .into_iter()
+        .flat_map(|shortcuts| shortcuts.keys())
+        .filter(|name| name.starts_with(&typed))
+        .cloned()
+        .collect();
+    names.sort();
+    names.truncate(MAX_AUTOCOMPLETE_CHOICES);
+    names
+}
+
+fn describe_shortcut(shortcut: &Shortcut) -> String {
+    let mut description = format!(
+        "/{} {}#{} ({})",
+        shortcut.command, shortcut.player_name, shortcut.tag, shortcut.platform
+    );
+    if let Some(game_count) = shortcut.game_count {
+        description.push_str(&format!(" {} games", game_count));
+    }
+    description
+}
@@ -0,0 +1,377 @@
+use std::collections::HashMap;
+
+use reqwest::Client;
+use serenity::all::Colour;
+
+use crate::{
+    matches::{fetch_matches, skipped_games_line},
+    shared::{
+        requests::request_for_puuid,
+        store::{LinkedAccount, Store},
+        types::{AccountInfoContext, DiscordOutput, InfoDto, MatchDto, ParticipantDto, Platform},
+        util::RateLimiter,
+    },
+};
+
+const MAX_TABLE_ROWS: usize = 10;
+
+/// Totals for one champion, role, etc.
+pub type NamedTotals = (String, PerformanceTotals);
+
+/// Running totals for a set of games, averaged when displayed
+#[derive(Debug, Default, Clone)]
+pub struct PerformanceTotals {
+    pub games: u32,
+    pub wins: u32,
+    pub kills: i32,
+    pub deaths: i32,
+    pub assists: i32,
+    pub creep_score: i32,
+    pub vision_score: i32,
+    pub minutes: f64,
+    damage_share_sum: f64,
+    kill_participation_sum: f64,
+}
+
+impl PerformanceTotals {
+    /// Remakes are skipped so they don't drag winrate down
+    pub fn add(&mut self, me: &ParticipantDto, info: &InfoDto) {
+        if me.is_remake() {
+            return;
+        }
+        let team = info.participants.iter().filter(|p| p.team_id == me.team_id);
+        let team_kills: i32 = team.clone().map(|p| p.kills).sum();
+        let team_damage: i32 = team.map(|p| p.total_damage_dealt_to_champions).sum();
+
+        self.games += 1;
+        self.wins += me.win as u32;
+        self.kills += me.kills;
+        self.deaths += me.deaths;
+        self.assists += me.assists;
+        self.creep_score += me.total_minions_killed + me.neutral_minions_killed;
+        self.vision_score += me.vision_score;
+        self.minutes += info.game_duration as f64 / 60.0;
+        if team_damage > 0 {
+            self.damage_share_sum += me.total_damage_dealt_to_champions as f64 / team_damage as f64;
+        }
+        if team_kills > 0 {
+            self.kill_participation_sum += (me.kills + me.assists) as f64 / team_kills as f64;
+        }
+    }
+
+    pub fn winrate(&self) -> f64 {
+        if self.games == 0 {
+            return 0.0;
+        }
+        self.wins as f64 / self.games as f64 * 100.0
+    }
+
+    pub fn kda(&self) -> f64 {
+        (self.kills + self.assists) as f64 / self.deaths.max(1) as f64
+    }
+
+    pub fn cs_per_minute(&self) -> f64 {
+        if self.minutes == 0.0 {
+            return 0.0;
+        }
+        self.creep_score as f64 / self.minutes
+    }
+
+    pub fn vision_per_minute(&self) -> f64 {
+        if self.minutes == 0.0 {
+            return 0.0;
+        }
+        self.vision_score as f64 / self.minutes
+    }
+
+    pub fn damage_share(&self) -> f64 {
+        if self.games == 0 {
+            return 0.0;
+        }
+        self.damage_share_sum / self.games as f64 * 100.0
+    }
+
+    pub fn kill_participation(&self) -> f64 {
+        if self.games == 0 {
+            return 0.0;
+        }
+        self.kill_participation_sum / self.games as f64 * 100.0
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn handle_stats_command(
+    player_name: &str,
+    tag: &str,
+    platform: Platform,
+    game_count: i64,
+    api_key: &str,
+    client: &Client,
+    store: &Store,
+    limiter: &RateLimiter,
+) -> Result<DiscordOutput, Box<dyn std::error::Error>> {
+    limiter.acquire().await;
+    let puuid = request_for_puuid(player_name, tag, platform, api_key, client).await?;
+
+    let account_info_context = AccountInfoContext {
+        puuid: puuid.clone(),
+        player_name: player_name.to_string(),
+        tag: tag.to_string(),
+        platform,
+    };
+
+    let (matches, skipped) = fetch_matches(
+        game_count,
+        api_key,
+        account_info_context,
+        client,
+        store,
+        limiter,
+    )
+    .await?;
+
+    let mut output = get_stats_info(
+        &matches,
+        &[puuid.as_str()],
+        format!("{}#{}'s Stats", player_name, tag),
+    );
+    output.description.push_str(&skipped_games_line(skipped));
+    Ok(output)
+}
+
+/// `/stats account:all`, the most recent `game_count` games across all of a user's accounts
+pub async fn handle_all_accounts_stats_command(
+    accounts: &[LinkedAccount],
+    game_count: i64,
+    api_key: &str,
+    client: &Client,
+    store: &Store,
+    limiter: &RateLimiter,
+) -> Result<DiscordOutput, Box<dyn std::error::Error>> {
+    let mut matches = vec![];
+    let mut skipped = 0;
+    for account in accounts {
+        let account_info_context = AccountInfoContext {
+            puuid: account.puuid.clone(),
+            player_name: account.player_name.clone(),
+            tag: account.tag.clone(),
+            platform: account.platform,
+        };
+        let (account_matches, account_skipped) = fetch_matches(
+            game_count,
+            api_key,
+            account_info_context,
+            client,
+            store,
+            limiter,
+        )
+        .await?;
+        matches.extend(account_matches);
+        skipped += account_skipped;
+    }
+    matches.sort_by_key(|match_dto| std::cmp::Reverse(match_dto.info.game_end_timestamp));
+    matches.truncate(game_count as usize);
+
+    let puuids: Vec<&str> = accounts
+        .iter()
+        .map(|account| account.puuid.as_str())
+        .collect();
+    let title = match accounts {
+        [primary] => format!("{}'s Stats", primary.riot_id()),
+        [primary, ..] => format!(
+            "{}'s Stats - {} accounts combined",
+            primary.riot_id(),
+            accounts.len()
+        ),
+        [] => return Err("No linked accounts".into()),
+    };
+    let mut output = get_stats_info(&matches, &puuids, title);
+    output.description.push_str(&skipped_games_line(skipped));
+    Ok(output)
+}
+
+/// `puuids` are the accounts of one person, usually just the one looked up
+pub fn get_stats_info(matches: &[MatchDto], puuids: &[&str], title: String) -> DiscordOutput {
+    let mut overall = PerformanceTotals::default();
+    let by_champion = aggregate_by_accounts(matches, puuids, |me, _| me.champion_name.clone());
+    let by_role = aggregate_by_accounts(matches, puuids, role_name);
+    for match_dto in matches {
+        if let Some(me) = find_account_participant(match_dto, puuids) {
+            overall.add(me, &match_dto.info);
+        }
+    }
+
+    if overall.games == 0 {
+        return DiscordOutput::new(
+            Colour::LIGHT_GREY,
+            "No games found".to_string(),
+            vec![],
+            "".to_string(),
+            title,
+            "".to_string(),
+        );
+    }
+
+    let mut description = format!(
+        "{} games - {:.0}% WR - {:.2} KDA - {:.1} CS/min",
+        overall.games,
+        overall.winrate(),
+        overall.kda(),
+        overall.cs_per_minute()
+    );
+    if let Some((best, worst)) = best_and_worst(&by_champion) {
+        description.push_str(&format!(
+            "\n🏆 Best: {} ({:.0}% in {})\n💀 Worst: {} ({:.0}% in {})",
+            best.0,
+            best.1.winrate(),
+            best.1.games,
+            worst.0,
+            worst.1.winrate(),
+            worst.1.games
+        ));
+    }
+
+    let fields = vec![
+        (
+            "Champions".to_string(),
+            format!("```{}```", stats_table(&by_champion)),
+            false,
+        ),
+        (
+            "Roles".to_string(),
+            format!("```{}```", stats_table(&by_role)),
+            false,
+        ),
+    ];
+
+    DiscordOutput::new(
+        if overall.winrate() >= 50.0 {
+            Colour::DARK_GREEN
+        } else {
+            Colour::DARK_RED
+        },
+        description,
+        fields,
+        "".to_string(),
+        title,
+        "".to_string(),
+    )
+}
+
+pub fn find_participant<'a>(match_dto: &'a MatchDto, puuid: &str) -> Option<&'a ParticipantDto> {
+    match_dto
+        .info
+        .participants
+        .iter()
+        .find(|p| p.puuid == puuid)
+}
+
+/// Like `find_participant` for whichever of one person's accounts played the match
+pub fn find_account_participant<'a>(
+    match_dto: &'a MatchDto,
+    puuids: &[&str],
+) -> Option<&'a ParticipantDto> {
+    match_dto
+        .info
+        .participants
+        .iter()
+        .find(|p| puuids.contains(&p.puuid.as_str()))
+}
+
+/// Groups the player's games by `key`, most played first
+pub fn aggregate_by<F>(matches: &[MatchDto], puuid: &str, key: F) -> Vec<NamedTotals>
+where
+    F: Fn(&ParticipantDto, &InfoDto) -> String,
+{
+    aggregate_by_accounts(matches, &[puuid], key)
+}
+
+fn aggregate_by_accounts<F>(matches: &[MatchDto], puuids: &[&str], key: F) -> Vec<NamedTotals>
+where
+    F: Fn(&ParticipantDto, &InfoDto) -> String,
+{
+    let mut totals: HashMap<String, PerformanceTotals> = HashMap::new();
+    for match_dto in matches {
+        if let Some(me) = find_account_participant(match_dto, puuids).filter(|me| !me.is_remake()) {
+            totals
+                .entry(key(me, &match_dto.info))
+                .or_default()
+                .add(me, &match_dto.info);
+        }
+    }
+
+    let mut totals: Vec<NamedTotals> = totals.into_iter().collect();
+    totals.sort_by(|(a_name, a), (b_name, b)| b.games.cmp(&a.games).then(a_name.cmp(b_name)));
+    totals
+}
+
+/// Current run of wins (`true`) or losses, outcomes newest game first
+pub fn current_streak(outcomes: &[bool]) -> Option<(bool, usize)> {
+    let latest = *outcomes.first()?;
+    Some((
+        latest,
+        outcomes.iter().take_while(|win| **win == latest).count(),
+    ))
+}
+
+pub fn role_name(me: &ParticipantDto, info: &InfoDto) -> String {
+    if info.game_mode != "CLASSIC" {
+        return info.game_mode.clone();
+    }
+    match me.team_position.as_str() {
+        "TOP" => "Top",
+        "JUNGLE" => "Jungle",
+        "MIDDLE" => "Mid",
+        "BOTTOM" => "Adc",
+        "UTILITY" => "Support",
+        _ => "Other",
+    }
+    .to_string()
+}
+
+/// Only champions played more than once are considered, unless every champion is a one-off
+fn best_and_worst(by_champion: &[NamedTotals]) -> Option<(&NamedTotals, &NamedTotals)> {
+    let repeated: Vec<&NamedTotals> = by_champion.iter().filter(|(_, t)| t.games > 1).collect();
+    let candidates = if repeated.len() > 1 {
+        repeated
+    } else {
+        by_champion.iter().collect()
+    };
+    if candidates.len() < 2 {
+        return None;
+    }
+
+    let score = |totals: &PerformanceTotals| (totals.winrate(), totals.kda());
+    let best = candidates
+        .iter()
+        .max_by(|(_, a), (_, b)| score(a).partial_cmp(&score(b)).unwrap())?;
+    let worst = candidates
+        .iter()
+        .min_by(|(_, a), (_, b)| score(a).partial_cmp(&score(b)).unwrap())?;
+
+    Some((best, worst))
+}
+
+pub fn stats_table(rows: &[NamedTotals]) -> String {
+    let header = format!(
+        "{:<12} {:>2} {:>4} {:>4} {:>4} {:>4} {:>4}",
+        "", "G", "WR", "KDA", "CS/m", "DMG", "KP"
+    );
+    let lines = rows.iter().take(MAX_TABLE_ROWS).map(|(name, totals)| {
+        format!(
+            "{:<12} {:>2} {:>3.0}% {:>4.1} {:>4.1} {:>3.0}% {:>3.0}%",
+            name.chars().take(12).collect::<String>(),
+            totals.games,
+            totals.winrate(),
+            totals.kda(),
+            totals.cs_per_minute(),
+            totals.damage_share(),
+            totals.kill_participation()
+        )
+    });
+
+    std::iter::once(header)
+        .chain(lines)
+        .collect::<Vec<String>>()
+        .join("\n")
+}
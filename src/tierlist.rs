@@ -67,5 +67,7 @@ pub async fn handle_tierlist_command(
         )],
         footer: "".to_string(),
         content: "".to_string(),
+        buttons: vec![],
+        attachments: vec![],
     })
 }
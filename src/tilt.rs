@@ -0,0 +1,160 @@
+use chrono::Utc;
+use serenity::all::{ChannelId, Colour, CreateMessage, Http};
+use tracing::warn;
+
+use crate::{
+    announcements::RANKED_SOLO_QUEUE_ID,
+    shared::{
+        store::{LinkedAccount, Store, TiltAlertSettings},
+        types::DiscordOutput,
+    },
+    stats::find_participant,
+};
+
+const RANKED_FLEX_QUEUE_ID: i64 = 440;
+const HOUR_MILLIS: i64 = 60 * 60 * 1000;
+const BREAK_SUGGESTIONS: [&str; 5] = [
+    "Maybe grab a snack, the ladder will still be there later 🍪",
+    "The enemy jungler can't gank you if you're out for a walk 🚶",
+    "Hydrate, stretch, touch some grass. Then queue up again 🌱",
+    "Even Faker takes breaks. Probably. Go get some water 💧",
+    "LP is temporary, a good night's sleep is forever 😴",
+];
+
+type PollError = Box<dyn std::error::Error + Send + Sync>;
+
+/// A guild with tilt alerts on that the account is linked in
+struct TiltCheck {
+    guild_id: u64,
+    channel: ChannelId,
+    tilt: TiltAlertSettings,
+    /// Newest loss of the last streak alerted on
+    alerted: Option<String>,
+    /// Discord user the account is linked to, mentioned in the alert
+    user_id: Option<u64>,
+}
+
+/// Solo and flex, the queues a losing streak costs LP in
+pub fn is_ranked_queue(queue_id: i64) -> bool {
+    queue_id == RANKED_SOLO_QUEUE_ID || queue_id == RANKED_FLEX_QUEUE_ID
+}
+
+/// Called by the announcement poller after caching an account's new games. Suggests a break
+/// in each of `guilds` with tilt alerts on once the account's latest ranked games are enough
+/// losses in a row within the configured window
+pub async fn check_tilt(
+    http: &Http,
+    account: &LinkedAccount,
+    guilds: &[(u64, ChannelId)],
+    store: &Store,
+) -> Result<(), PollError> {
+    let enabled: Vec<TiltCheck> = {
+        let data = store.read().await;
+        guilds
+            .iter()
+            .filter_map(|(guild_id, channel)| {
+                let settings = data.guild_settings.get(guild_id)?;
+                let user_id = data
+                    .guild_accounts(*guild_id)
+                    .into_iter()
+                    .find(|(_, linked)| linked.puuid == account.puuid)
+                    .map(|(user_id, _)| user_id);
+                settings.tilt_alerts.enabled.then(|| TiltCheck {
+                    guild_id: *guild_id,
+                    channel: *channel,
+                    tilt: settings.tilt_alerts.clone(),
+                    alerted: settings.tilt_alerted.get(&account.puuid).cloned(),
+                    user_id,
+                })
+            })
+            .collect()
+    };
+    let Some(longest_window) = enabled.iter().map(|check| check.tilt.window_hours).max() else {
+        return Ok(());
+    };
+
+    let now = Utc::now().timestamp_millis();
+    // Newest first, remakes don't break or extend a streak
+    let matches = store
+        .cached_matches(&account.puuid, now - longest_window * HOUR_MILLIS)
+        .await;
+    let ranked: Vec<(&str, i64, bool)> = matches
+        .iter()
+        .filter(|m| is_ranked_queue(m.info.queue_id))
+        .filter_map(|m| {
+            let me = find_participant(m, &account.puuid).filter(|me| !me.is_remake())?;
+            Some((
+                m.metadata.match_id.as_str(),
+                m.info.game_end_timestamp,
+                me.win,
+            ))
+        })
+        .collect();
+
+    for TiltCheck {
+        guild_id,
+        channel,
+        tilt,
+        alerted,
+        user_id,
+    } in enabled
+    {
+        let since = now - tilt.window_hours * HOUR_MILLIS;
+        let losses: Vec<&str> = ranked
+            .iter()
+            .take_while(|(_, ended, win)| !win && *ended >= since)
+            .map(|(match_id, ..)| *match_id)
+            .collect();
+        // Already alerted on this streak, a new alert needs the old loss out of the window
+        if losses.len() < tilt.losses
+            || alerted.is_some_and(|alerted| losses.contains(&alerted.as_str()))
+        {
+            continue;
+        }
+
+        store
+            .update(|data| {
+                data.guild_settings
+                    .entry(guild_id)
+                    .or_default()
+                    .tilt_alerted
+                    .insert(account.puuid.clone(), losses[0].to_string())
+            })
+            .await?;
+
+        let output = get_tilt_alert(account, user_id, losses.len(), tilt.window_hours);
+        let message = CreateMessage::new().embed(output.to_embed());
+        if let Err(err) = channel.send_message(http, message).await {
+            warn!("Could not post tilt alert in {}: {}", channel, err);
+        }
+    }
+
+    Ok(())
+}
+
+fn get_tilt_alert(
+    account: &LinkedAccount,
+    user_id: Option<u64>,
+    losses: usize,
+    window_hours: i64,
+) -> DiscordOutput {
+    let player = match user_id {
+        Some(user_id) => format!("<@{}>", user_id),
+        None => account.riot_id(),
+    };
+
+    DiscordOutput::new(
+        Colour::ORANGE,
+        format!(
+            "{} lost {} ranked games in a row in the last {} hours. {}",
+            player,
+            losses,
+            window_hours,
+            BREAK_SUGGESTIONS[losses % BREAK_SUGGESTIONS.len()]
+        ),
+        vec![],
+        "".to_string(),
+        format!("🧘 Time for a break, {}?", account.player_name),
+        "".to_string(),
+    )
+}
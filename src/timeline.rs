@@ -0,0 +1,366 @@
+use std::{collections::HashMap, str::FromStr};
+
+use plotters::prelude::*;
+use reqwest::Client;
+use serenity::all::Colour;
+use tracing::warn;
+
+use crate::{
+    shared::{
+        charts::{encode_png, register_fonts, CHART_BACKGROUND, CHART_HEIGHT, CHART_WIDTH},
+        requests::{request_for_puuid, request_match, request_timeline},
+        static_data::queue_name,
+        store::Store,
+        types::{DiscordOutput, MatchDto, ParticipantDto, Platform, TimelineDto},
+        util::RateLimiter,
+    },
+    stats::find_participant,
+};
+
+const LEAD_CHART_AHEAD: RGBColor = RGBColor(67, 181, 129);
+const LEAD_CHART_BEHIND: RGBColor = RGBColor(240, 71, 71);
+/// Minutes the player's lane lead is shown at
+const LANE_LEAD_MINUTES: [i64; 2] = [10, 15];
+
+#[allow(clippy::too_many_arguments)]
+pub async fn handle_timeline_command(
+    match_id: &str,
+    player_name: &str,
+    tag: &str,
+    platform: Platform,
+    api_key: &str,
+    client: &Client,
+    store: &Store,
+    limiter: &RateLimiter,
+) -> Result<DiscordOutput, Box<dyn std::error::Error>> {
+    limiter.acquire().await;
+    let puuid = request_for_puuid(player_name, tag, platform, api_key, client).await?;
+
+    // Match ids start with the platform they were played on, e.g. `EUW1_123`
+    let match_platform = match_id
+        .split_once('_')
+        .and_then(|(platform, _)| Platform::from_str(platform).ok())
+        .unwrap_or(platform);
+    let match_dto = match store.load_match(match_id) {
+        Some(match_dto) => match_dto,
+        None => {
+            limiter.acquire().await;
+            let match_dto = request_match(match_id, match_platform, api_key, client).await?;
+            store
+                .save_matches(&puuid, std::slice::from_ref(&match_dto))
+                .await?;
+            match_dto
+        }
+    };
+    let me = find_participant(&match_dto, &puuid)
+        .ok_or_else(|| format!("{}#{} did not play in {}", player_name, tag, match_id))?;
+    let timeline =
+        fetch_timeline(match_id, match_platform, api_key, client, store, limiter).await?;
+
+    let team_of: HashMap<i32, u32> = match_dto
+        .info
+        .participants
+        .iter()
+        .map(|p| (p.participant_id, p.team_id))
+        .collect();
+    let gold_leads: Vec<(f64, i32)> = timeline
+        .info
+        .frames
+        .iter()
+        .map(|frame| {
+            let lead = frame
+                .participant_frames
+                .values()
+                .map(|p| match team_of.get(&p.participant_id) {
+                    Some(team_id) if *team_id == me.team_id => p.total_gold,
+                    _ => -p.total_gold,
+                })
+                .sum();
+            (frame.timestamp as f64 / 60_000.0, lead)
+        })
+        .collect();
+
+    let mut fields = get_objective_fields(&timeline, &match_dto, me);
+    let opponent = match_dto.info.participants.iter().find(|p| {
+        !me.team_position.is_empty()
+            && p.team_id != me.team_id
+            && p.team_position == me.team_position
+    });
+    if let Some(opponent) = opponent {
+        for minutes in LANE_LEAD_MINUTES {
+            if let Some((gold, xp)) = lane_lead(&timeline, me, opponent, minutes) {
+                fields.push((
+                    format!("Lane @{}", minutes),
+                    format!("{:+} gold, {:+} XP vs {}", gold, xp, opponent.champion_name),
+                    true,
+                ));
+            }
+        }
+    }
+
+    let duration = match_dto.info.game_duration;
+    let description = format!(
+        "{} - {} - {} - {}:{:02}",
+        me.champion_name,
+        if me.is_remake() {
+            "REMAKE"
+        } else if me.win {
+            "WIN"
+        } else {
+            "LOSS"
+        },
+        queue_name(match_dto.info.queue_id),
+        duration / 60,
+        duration % 60
+    );
+    let chart = draw_gold_chart(&gold_leads)?;
+
+    Ok(DiscordOutput::new(
+        if me.win {
+            Colour::DARK_GREEN
+        } else {
+            Colour::DARK_RED
+        },
+        description,
+        fields,
+        "".to_string(),
+        format!("{}#{}'s Timeline - {}", player_name, tag, match_id),
+        "".to_string(),
+    )
+    .with_attachment("timeline.png".to_string(), chart))
+}
+
+/// Reads the match's timeline from the store, requesting and caching it through `limiter`
+/// on a miss. Timelines don't change once a game is over
+pub async fn fetch_timeline(
+    match_id: &str,
+    platform: Platform,
+    api_key: &str,
+    client: &Client,
+    store: &Store,
+    limiter: &RateLimiter,
+) -> Result<TimelineDto, Box<dyn std::error::Error>> {
+    if let Some(timeline) = store.load_timeline(match_id) {
+        return Ok(timeline);
+    }
+
+    limiter.acquire().await;
+    let timeline = request_timeline(match_id, platform, api_key, client).await?;
+    if let Err(err) = store.save_timeline(match_id, &timeline).await {
+        warn!("Could not cache the timeline of {}: {}", match_id, err);
+    }
+    Ok(timeline)
+}
+
+/// First blood, first tower, dragons, heralds and barons with their timings
+fn get_objective_fields(
+    timeline: &TimelineDto,
+    match_dto: &MatchDto,
+    me: &ParticipantDto,
+) -> Vec<(String, String, bool)> {
+    let participant = |participant_id: i32| {
+        match_dto
+            .info
+            .participants
+            .iter()
+            .find(|p| p.participant_id == participant_id)
+    };
+    let side = |team_id: u32| {
+        if team_id == me.team_id {
+            "Ally"
+        } else {
+            "Enemy"
+        }
+    };
+
+    let mut first_blood = None;
+    let mut first_tower = None;
+    let mut dragons = vec![];
+    let mut barons_and_heralds = vec![];
+    for event in timeline.info.frames.iter().flat_map(|frame| &frame.events) {
+        let time = format_timestamp(event.timestamp);
+        match event.event_type.as_str() {
+            "CHAMPION_KILL" if first_blood.is_none() => {
+                let killer = event.killer_id.and_then(participant);
+                let victim = event.victim_id.and_then(participant);
+                first_blood = Some(match (killer, victim) {
+                    (Some(killer), Some(victim)) => format!(
+                        "{} - {} ({}) on {}",
+                        time,
+                        killer.champion_name,
+                        side(killer.team_id),
+                        victim.champion_name
+                    ),
+                    // Executed by a tower or minions
+                    (None, Some(victim)) => format!("{} - {} executed", time, victim.champion_name),
+                    _ => time,
+                });
+            }
+            "BUILDING_KILL"
+                if first_tower.is_none()
+                    && event.building_type.as_deref() == Some("TOWER_BUILDING") =>
+            {
+                // The event's team is the one that lost the tower
+                first_tower = event.team_id.map(|team_id| {
+                    format!(
+                        "{} - {}",
+                        time,
+                        if team_id == me.team_id {
+                            "Enemy"
+                        } else {
+                            "Ally"
+                        }
+                    )
+                });
+            }
+            "ELITE_MONSTER_KILL" => {
+                let team = event.killer_team_id.map(side).unwrap_or("?");
+                match event.monster_type.as_deref() {
+                    Some("DRAGON") => dragons.push(format!(
+                        "{} - {} {}",
+                        time,
+                        team,
+                        dragon_name(event.monster_sub_type.as_deref())
+                    )),
+                    Some("RIFTHERALD") => {
+                        barons_and_heralds.push(format!("{} - {} Herald", time, team))
+                    }
+                    Some("BARON_NASHOR") => {
+                        barons_and_heralds.push(format!("{} - {} Baron", time, team))
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let or_none = |lines: Vec<String>| {
+        if lines.is_empty() {
+            "None".to_string()
+        } else {
+            lines.join("\n")
+        }
+    };
+    vec![
+        (
+            "🩸 First Blood".to_string(),
+            first_blood.unwrap_or_else(|| "None".to_string()),
+            true,
+        ),
+        (
+            "🗼 First Tower".to_string(),
+            first_tower.unwrap_or_else(|| "None".to_string()),
+            true,
+        ),
+        ("\u{200b}".to_string(), "\u{200b}".to_string(), true),
+        ("🐉 Dragons".to_string(), or_none(dragons), true),
+        (
+            "🟣 Herald & Baron".to_string(),
+            or_none(barons_and_heralds),
+            true,
+        ),
+    ]
+}
+
+/// Gold and XP difference to the lane opponent at the first frame past `minutes`
+fn lane_lead(
+    timeline: &TimelineDto,
+    me: &ParticipantDto,
+    opponent: &ParticipantDto,
+    minutes: i64,
+) -> Option<(i32, i32)> {
+    let frame = timeline
+        .info
+        .frames
+        .iter()
+        .find(|frame| frame.timestamp >= minutes * 60_000)?;
+    let me = frame
+        .participant_frames
+        .get(&me.participant_id.to_string())?;
+    let opponent = frame
+        .participant_frames
+        .get(&opponent.participant_id.to_string())?;
+    Some((me.total_gold - opponent.total_gold, me.xp - opponent.xp))
+}
+
+fn dragon_name(monster_sub_type: Option<&str>) -> &'static str {
+    match monster_sub_type {
+        Some("FIRE_DRAGON") => "Infernal",
+        Some("EARTH_DRAGON") => "Mountain",
+        Some("WATER_DRAGON") => "Ocean",
+        Some("AIR_DRAGON") => "Cloud",
+        Some("HEXTECH_DRAGON") => "Hextech",
+        Some("CHEMTECH_DRAGON") => "Chemtech",
+        Some("ELDER_DRAGON") => "Elder",
+        _ => "Dragon",
+    }
+}
+
+fn format_timestamp(timestamp: i64) -> String {
+    let seconds = timestamp / 1000;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// Team gold lead per minute, filled green while ahead and red while behind
+fn draw_gold_chart(gold_leads: &[(f64, i32)]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    register_fonts();
+
+    let end = gold_leads.last().map(|(minute, _)| *minute).unwrap_or(1.0);
+    // Symmetric around zero, padded to whole thousands
+    let max_lead = gold_leads
+        .iter()
+        .map(|(_, lead)| lead.abs())
+        .max()
+        .unwrap_or(0)
+        .max(1000);
+    let max_lead = (max_lead / 1000 + 1) * 1000;
+
+    let mut buffer = vec![0; (CHART_WIDTH * CHART_HEIGHT * 3) as usize];
+    {
+        let root = BitMapBackend::with_buffer(&mut buffer, (CHART_WIDTH, CHART_HEIGHT))
+            .into_drawing_area();
+        root.fill(&CHART_BACKGROUND)?;
+
+        let mut chart = ChartBuilder::on(&root)
+            .margin(20)
+            .x_label_area_size(30)
+            .y_label_area_size(60)
+            .build_cartesian_2d(0.0..end, -max_lead..max_lead)?;
+
+        chart
+            .configure_mesh()
+            .x_labels(10)
+            .x_label_formatter(&|minute| format!("{:.0}m", minute))
+            .y_labels(9)
+            .y_label_formatter(&|lead| format!("{:+.1}k", *lead as f64 / 1000.0))
+            .label_style(("sans-serif", 14).into_font().color(&WHITE))
+            .axis_style(WHITE.mix(0.5))
+            .bold_line_style(WHITE.mix(0.15))
+            .light_line_style(TRANSPARENT)
+            .draw()?;
+
+        chart.draw_series(AreaSeries::new(
+            gold_leads
+                .iter()
+                .map(|(minute, lead)| (*minute, (*lead).max(0))),
+            0,
+            LEAD_CHART_AHEAD.mix(0.6),
+        ))?;
+        chart.draw_series(AreaSeries::new(
+            gold_leads
+                .iter()
+                .map(|(minute, lead)| (*minute, (*lead).min(0))),
+            0,
+            LEAD_CHART_BEHIND.mix(0.6),
+        ))?;
+        chart.draw_series(LineSeries::new(
+            gold_leads.iter().copied(),
+            WHITE.stroke_width(2),
+        ))?;
+
+        root.present()?;
+    }
+
+    encode_png(buffer, CHART_WIDTH, CHART_HEIGHT)
+}